@@ -1 +1,2 @@
+pub mod nonce;
 pub mod token;
@@ -0,0 +1,173 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// unit of the clock the nonce is taken from, kraken only needs it to go up
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum NoncePrecision {
+    #[default]
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl NoncePrecision {
+    fn now(&self) -> u128 {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        match self {
+            NoncePrecision::Millis => time.as_millis(),
+            NoncePrecision::Micros => time.as_micros(),
+            NoncePrecision::Nanos => time.as_nanos(),
+        }
+    }
+}
+
+// strictly increasing nonces, shared by every clone of a Kraken client.
+// when the clock stalls or steps back the last nonce is bumped by one instead
+#[derive(Debug)]
+pub struct NonceSource {
+    precision: NoncePrecision,
+    last: Mutex<u128>,
+    path: Option<PathBuf>,
+}
+
+impl Default for NonceSource {
+    fn default() -> Self {
+        Self::new(NoncePrecision::default())
+    }
+}
+
+impl NonceSource {
+    pub fn new(precision: NoncePrecision) -> Self {
+        Self {
+            precision,
+            last: Mutex::new(0),
+            path: None,
+        }
+    }
+
+    // continues from the nonce stored at `path`, a missing file starts from the clock
+    pub fn persisted(precision: NoncePrecision, path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+
+        let last = match fs::read_to_string(&path) {
+            Ok(stored) => stored
+                .trim()
+                .parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "stored nonce is not a number"))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => 0,
+            Err(error) => return Err(error),
+        };
+
+        Ok(Self {
+            precision,
+            last: Mutex::new(last),
+            path: Some(path),
+        })
+    }
+
+    pub fn precision(&self) -> NoncePrecision {
+        self.precision
+    }
+
+    pub fn last(&self) -> u128 {
+        *self.last.lock().unwrap()
+    }
+
+    pub fn next(&self) -> u128 {
+        let mut last = self.last.lock().unwrap();
+        *last = self.precision.now().max(*last + 1);
+        *last
+    }
+
+    // writes the last nonce handed out, also done when the source is dropped
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => fs::write(path, self.last().to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for NonceSource {
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc, thread};
+
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("nonce-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn concurrent_callers_get_strictly_increasing_nonces() {
+        let source = Arc::new(NonceSource::new(NoncePrecision::Millis));
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let source = source.clone();
+                thread::spawn(move || (0..1000).map(|_| source.next()).collect::<Vec<u128>>())
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for thread in threads {
+            let nonces = thread.join().unwrap();
+            assert!(nonces.windows(2).all(|pair| pair[0] < pair[1]));
+            seen.extend(nonces);
+        }
+
+        // far more calls than milliseconds passed, so most of them were bumps
+        assert_eq!(seen.len(), 8000);
+        assert_eq!(seen.iter().max(), Some(&source.last()));
+    }
+
+    #[test]
+    fn persisted_source_resumes_above_the_last_nonce() {
+        let path = temp_path();
+        let ahead = NoncePrecision::Millis.now() * 1000;
+        fs::write(&path, ahead.to_string()).unwrap();
+
+        {
+            let source = NonceSource::persisted(NoncePrecision::Millis, &path).unwrap();
+            assert_eq!(source.next(), ahead + 1);
+            assert_eq!(source.next(), ahead + 2);
+        }
+
+        // dropping the source stored the last nonce
+        assert_eq!(fs::read_to_string(&path).unwrap(), (ahead + 2).to_string());
+
+        let resumed = NonceSource::persisted(NoncePrecision::Millis, &path).unwrap();
+        assert_eq!(resumed.next(), ahead + 3);
+
+        fs::write(&path, "not a nonce").unwrap();
+        let error = NonceSource::persisted(NoncePrecision::Millis, &path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        drop(resumed);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_starts_from_the_clock() {
+        let path = temp_path();
+        let before = NoncePrecision::Micros.now();
+
+        let source = NonceSource::persisted(NoncePrecision::Micros, &path).unwrap();
+        assert!(source.next() >= before);
+
+        drop(source);
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
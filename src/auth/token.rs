@@ -4,15 +4,14 @@ use ring::hmac;
 
 use serde_json::json;
 use sha2::{Digest, Sha256};
-use std::time::SystemTimeError;
-use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
+use crate::auth::nonce::NonceSource;
+use crate::fetch::error::ExchangeError;
+use crate::fetch::limiter::RateLimitError;
+use crate::fetch::urls::WEBSOCKET_TOKEN_URL;
 use crate::types::types::{Nonce, WebSocketKey};
 
-const WEBSOCKET_TOKEN: &'static str = "/0/private/GetWebSocketsToken";
-const BASE_URL: &'static str = "https://api.kraken.com";
-
 #[derive(Debug, Error)]
 pub enum CreateSignError {
     #[error("failed to serialize payload")]
@@ -30,8 +29,11 @@ pub enum GetWebSocketError {
     #[error("failed to fetch data from request")]
     GetHttpRequest(#[from] reqwest::Error),
 
-    #[error("SystemTime before UNIX EPOCH!")]
-    TimeError(#[from] SystemTimeError),
+    #[error(transparent)]
+    Exchange(#[from] ExchangeError),
+
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitError),
 }
 
 pub fn get_kraken_signature<T: serde::Serialize>(path: &str, payload: &T, private_key: &str, nonce: u128) -> Result<String, CreateSignError> {
@@ -41,7 +43,7 @@ pub fn get_kraken_signature<T: serde::Serialize>(path: &str, payload: &T, privat
     let mut message = path.to_string().into_bytes();
     message.extend(hashed_data);
 
-    let base_key = BASE64_STANDARD.decode(&private_key.as_bytes())?;
+    let base_key = BASE64_STANDARD.decode(private_key.as_bytes())?;
     let hmac_key = hmac::Key::new(hmac::HMAC_SHA512, &base_key);
 
     let signed_digest = hmac::sign(&hmac_key, &message);
@@ -50,22 +52,32 @@ pub fn get_kraken_signature<T: serde::Serialize>(path: &str, payload: &T, privat
     Ok(sign_key)
 }
 
-pub async fn get_websocket_token(client: &Client, public_key: &str, private_key: &str) -> Result<WebSocketKey, GetWebSocketError> {
-    let nonce = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+pub async fn get_websocket_token(
+    client: &Client,
+    base_url: &str,
+    public_key: &str,
+    private_key: &str,
+    nonces: &NonceSource,
+) -> Result<WebSocketKey, GetWebSocketError> {
+    let nonce = nonces.next();
 
     let body = Nonce {
         nonce: nonce.to_string(),
     };
 
-    let signature = get_kraken_signature(WEBSOCKET_TOKEN, &body, &private_key, nonce)?;
+    let signature = get_kraken_signature(WEBSOCKET_TOKEN_URL, &body, private_key, nonce)?;
 
     #[rustfmt::skip]
-    let res: WebSocketKey = client.post(format!("{}{}", BASE_URL, WEBSOCKET_TOKEN))
+    let res: WebSocketKey = client.post(format!("{}{}", base_url, WEBSOCKET_TOKEN_URL))
         .header("Content-Type", "application/json")
         .header("Accept", "application/json")
         .header("API-Key", public_key)
         .header("API-Sign", &signature)
         .json(&body).send().await?.json().await?;
 
+    if let Some(error) = ExchangeError::from_errors(&res.error) {
+        return Err(error.into());
+    }
+
     Ok(res)
 }
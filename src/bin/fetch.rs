@@ -16,7 +16,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let kraken_arc = Arc::new(kraken);
 
 
-    let candle = Trades::new(kraken_arc);
+    let mut trades = Trades::new(kraken_arc).await?;
+    let added = trades.update_trades().await?;
+    println!("{} trades, {} new since the first page", trades.trades.len(), added);
 
     // let body = TradeHistoryBody {
     //     ..Default::default()   
@@ -30,7 +32,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     //     cl_ord_id: Some(id),
     //     ordertype: OrderType::Market,
     //     type_field: Side::SELL,
-    //     volume: "6".into(),
+    //     volume: "6".parse().unwrap(),
     //     pair: "XRPEUR".into(),
     //     ..Default::default()
     // };
@@ -0,0 +1,19 @@
+use std::error::Error;
+
+use broken_bolt::{MOCK_PRIVATE_KEY, MOCK_PUBLIC_KEY, MockKraken};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let server = MockKraken::new().start().await?;
+    let endpoints = server.endpoints();
+
+    println!("PUBLIC_KEY={}", MOCK_PUBLIC_KEY);
+    println!("PRIVATE_KEY={}", MOCK_PRIVATE_KEY);
+    println!("KRAKEN_REST_URL={}", endpoints.rest);
+    println!("KRAKEN_WS_URL={}", endpoints.ws_public);
+    println!("KRAKEN_WS_AUTH_URL={}", endpoints.ws_private);
+
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
@@ -13,30 +13,58 @@ use std::error::Error;
 use std::process::exit;
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, Mutex, mpsc};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{io, thread};
 
 use broken_bolt::{
-    App, Button, Candle, CandleStick, Ch, Channel, Incoming, KraSoc, Kraken, Message, OrderBook, OrderBookType, Socket, TickerType, Trades, ui,
+    App, BookDepth, Button, Candle, CandleStick, Ch, Channel, KraSoc, Kraken, MarketTrades, Message, OrderBook, OrderBookType, Socket, Ticker, TickerType,
+    TradeData, Trades, WATCHLIST, WsEvent, ui,
 };
 
-async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, event: Receiver<State>) -> io::Result<bool> {
+// older candles rebuilt from trades per press of 'h'
+const HISTORY_STEP: usize = 100;
+// how often the executed trades are fetched again, TradesHistory is one of the costlier private calls
+const TRADES_REFRESH: Duration = Duration::from_secs(30);
+
+async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, socket: &mut Socket, event: Receiver<State>) -> io::Result<bool> {
     let (event_tx, event_rx) = mpsc::channel::<Message>();
 
     let mut mouse_event: Option<MouseEvent> = None;
+    let mut trades_refreshed = Instant::now();
 
     loop {
+        if trades_refreshed.elapsed() >= TRADES_REFRESH {
+            let _ = app.trades.update_trades().await;
+            trades_refreshed = Instant::now();
+        }
+
         while let Ok(state) = event.try_recv() {
             match state {
                 State::Input(key_event) => {
                     if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Char('q') {
                         return Ok(true);
                     }
+                    if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Char('d') {
+                        app.depth_chart = !app.depth_chart;
+                    }
+                    if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Char('h') {
+                        let _ = app.candle.load_history(HISTORY_STEP).await;
+                    }
                 }
                 State::Mouse(mouse) => mouse_event = Some(mouse),
 
-                State::OrderBook(update) => app.orderbook.stream(update),
+                State::OrderBook(update) => {
+                    if app.orderbook.stream(update).is_err() {
+                        let _ = socket.resubscribe(&app.orderbook.channel()).await;
+                    }
+                }
                 State::Candles(update) => app.candle.web_stream(update),
+                State::Trades(update) => app.market_trades.stream(update),
+                State::Ticker(update) => app.ticker.stream(update),
+                State::Resync => {
+                    app.orderbook.clear();
+                    let _ = app.candle.resync().await;
+                }
             }
         }
 
@@ -45,11 +73,24 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, event: R
         while let Ok(update) = event_rx.try_recv() {
             match update {
                 Message::UpdateCandlesPair(pair) => {
+                    let _ = socket.unsubscribe(app.orderbook.channel()).await;
+                    let _ = app.orderbook.update_pair(&pair).await;
+                    let _ = socket.subscribe(app.orderbook.channel()).await;
+
+                    let _ = socket.unsubscribe(app.market_trades.channel()).await;
+                    app.market_trades.update_pair(&pair);
+                    let _ = socket.subscribe(app.market_trades.channel()).await;
+
+                    if let Some(channel) = app.ticker.watch(&pair) {
+                        let _ = socket.subscribe(channel).await;
+                    }
+
                     let _ = app.candle.update_pair(&pair).await;
-                },
+                }
                 Message::UpdateCandlesInterval(interval) => {
                     let _ = app.candle.update_interval(interval).await;
                 }
+                Message::UpdateCandleType(candle_type) => app.candle.update_candle_type(candle_type),
             }
         }
     }
@@ -60,6 +101,9 @@ enum State {
     Mouse(MouseEvent),
     OrderBook(OrderBookType),
     Candles(KraSoc<CandleStick>),
+    Trades(KraSoc<TradeData>),
+    Ticker(TickerType),
+    Resync,
 }
 
 #[tokio::main]
@@ -77,38 +121,39 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let extra = ("interval".to_string(), serde_json::to_value(interval).unwrap());
 
     let ohlc_channel = Channel::new(Ch::OHLC, vec![pair], Some(HashMap::from([extra])));
-    let orderbook_channel = Channel::new(Ch::BOOK, vec![pair], None);
-
-    let mut web = Socket::new(vec![orderbook_channel]);
-
-    web.start().await.expect("Error socket {}");
-    web.subscribe_to_channels(false).await;
 
     let mut kraken = Kraken::from_env()?;
+    let endpoints = kraken.get_endpoints().clone();
     let kraken_arc = Arc::new(kraken);
 
     let mut trades = Trades::new(kraken_arc.clone()).await.expect("Failed to init trades");
-    let mut orderbook = OrderBook::new(kraken_arc.clone(), pair).await.expect("Failed to init orderbook");
+    let mut orderbook = OrderBook::new(kraken_arc.clone(), pair, BookDepth::D10).await.expect("Failed to init orderbook");
     let mut candles = Candle::new(kraken_arc.clone(), pair, interval).await.expect("Failed to init candle");
 
+    let market_trades = MarketTrades::new(pair);
+    let mut ticker = Ticker::new(&WATCHLIST);
+    ticker.watch(pair);
+
+    let mut web = Socket::new(vec![orderbook.channel(), market_trades.channel(), ticker.channel()]).endpoints(endpoints);
+
+    web.start().await.expect("Error socket {}");
+    web.subscribe_to_channels(false).await;
+
     let (event_tx, event_rx) = mpsc::channel::<State>();
-    let mut app = App::new(orderbook.clone(), candles.clone(), trades);
+    let mut app = App::new(orderbook.clone(), candles.clone(), trades, market_trades, ticker);
 
     let update_key = event_tx.clone();
     thread::spawn(move || read_user_input(update_key));
 
     let update_state = event_tx.clone();
+    let mut msg = web.recv_msg.take().expect("msg");
     let main = tokio::spawn(async move {
-        let mut msg = web.recv_msg.take().expect("msg");
-        while let Some(data) = msg.recv().await {
-            if (data.channel == "subscribe" || data.channel == "heartbeat" || data.channel == "status") {
-                continue;
-            }
-            incoming(data, &mut web, &mut orderbook, &mut candles, &update_state).await
+        while let Some(event) = msg.recv().await {
+            incoming(event, &mut orderbook, &mut candles, &update_state).await
         }
     });
 
-    let _ = run_app(&mut terminal, &mut app, event_rx).await;
+    let _ = run_app(&mut terminal, &mut app, &mut web, event_rx).await;
 
     // v iu stuff
 
@@ -129,18 +174,23 @@ fn read_user_input(sender: Sender<State>) {
     }
 }
 
-async fn incoming(msg: Incoming, soc: &mut Socket, orderbook: &mut OrderBook, candles: &mut Candle, update_ui: &Sender<State>) {
-    if msg.channel == "ticker" {
-        let ticker: TickerType = serde_json::from_str(&msg.message.to_string()).unwrap();
-    }
-
-    if msg.channel == "book" {
-        let ob_data: OrderBookType = serde_json::from_str(&msg.message.to_string()).unwrap();
-        update_ui.send(State::OrderBook(ob_data.clone()));
-    }
-
-    if msg.channel == "ohlc" {
-        let ohlc_data: KraSoc<CandleStick> = serde_json::from_str(&msg.message.to_string()).unwrap();
-        update_ui.send(State::Candles(ohlc_data.clone()));
+async fn incoming(event: WsEvent, orderbook: &mut OrderBook, candles: &mut Candle, update_ui: &Sender<State>) {
+    match event {
+        WsEvent::Book(ob_data) => {
+            update_ui.send(State::OrderBook(ob_data));
+        }
+        WsEvent::Ohlc(ohlc_data) => {
+            update_ui.send(State::Candles(ohlc_data));
+        }
+        WsEvent::Trade(trade_data) => {
+            update_ui.send(State::Trades(trade_data));
+        }
+        WsEvent::Ticker(ticker_data) => {
+            update_ui.send(State::Ticker(ticker_data));
+        }
+        WsEvent::Resync => {
+            update_ui.send(State::Resync);
+        }
+        _ => {}
     }
 }
@@ -5,6 +5,8 @@ use nestify::nest;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::types::decimal::Decimal;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct TradeHistoryBody {
@@ -66,7 +68,7 @@ impl fmt::Display for BalanceType {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum OrderType {
     Limit,
@@ -100,7 +102,7 @@ pub enum TimeInForce {
     GTD, // Good-'til-date
 }
 
-#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Side {
     #[default]
@@ -114,22 +116,22 @@ pub struct AddOrder {
     pub ordertype: OrderType,
     #[serde(rename = "type")]
     pub type_field: Side,
-    pub volume: String,
+    pub volume: Decimal,
     pub pair: String,
-    
+
     // optional
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cl_ord_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub price: Option<String>,
+    pub price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub asset_class: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "userref")]
     pub user_ref: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "displayvol")]
-    pub display_vol: Option<String>,
+    pub display_vol: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub price2: Option<String>,
+    pub price2: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trigger: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -153,3 +155,271 @@ pub struct AddOrder {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub validate: Option<bool>,
 }
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseTime {
+    Open,
+    Close,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct OpenOrdersBody {
+    pub trades: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userref: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
+    pub rebase_multiplier: BalanceType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ClosedOrdersBody {
+    pub trades: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userref: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ofs: Option<i64>,
+    #[serde(rename = "closetime")]
+    pub close_time: CloseTime,
+    pub consolidate_taker: bool,
+    pub without_count: bool,
+    pub rebase_multiplier: BalanceType,
+}
+
+impl Default for ClosedOrdersBody {
+    fn default() -> Self {
+        ClosedOrdersBody {
+            trades: false,
+            userref: None,
+            cl_ord_id: None,
+            start: None,
+            end: None,
+            ofs: None,
+            close_time: CloseTime::default(),
+            consolidate_taker: true,
+            without_count: false,
+            rebase_multiplier: BalanceType::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryOrdersBody {
+    // comma separated, up to 50 ids
+    pub txid: String,
+    pub trades: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userref: Option<i32>,
+    pub consolidate_taker: bool,
+    pub rebase_multiplier: BalanceType,
+}
+
+impl Default for QueryOrdersBody {
+    fn default() -> Self {
+        QueryOrdersBody {
+            txid: String::new(),
+            trades: false,
+            userref: None,
+            consolidate_taker: true,
+            rebase_multiplier: BalanceType::default(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerType {
+    #[default]
+    All,
+    Trade,
+    Deposit,
+    Withdrawal,
+    Transfer,
+    Margin,
+    Adjustment,
+    Rollover,
+    Spend,
+    Receive,
+    Settled,
+    Credit,
+    Staking,
+    Reward,
+    Dividend,
+    Sale,
+    Conversion,
+    // types kraken added after this list
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LedgersBody {
+    // comma separated, `all` for every asset
+    pub asset: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aclass: Option<String>,
+    #[serde(rename = "type")]
+    pub ledger_type: LedgerType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ofs: Option<i64>,
+    pub without_count: bool,
+    pub rebase_multiplier: BalanceType,
+}
+
+impl Default for LedgersBody {
+    fn default() -> Self {
+        LedgersBody {
+            asset: "all".to_string(),
+            aclass: None,
+            ledger_type: LedgerType::default(),
+            start: None,
+            end: None,
+            ofs: None,
+            without_count: false,
+            rebase_multiplier: BalanceType::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryLedgersBody {
+    // comma separated, up to 20 ids
+    pub id: String,
+    pub trades: bool,
+    pub rebase_multiplier: BalanceType,
+}
+
+// cancels the order and places a new one, the new order gets a new txid
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct EditOrder {
+    pub txid: String,
+    pub pair: String,
+
+    // optional
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userref: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "displayvol")]
+    pub display_vol: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price2: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "oflags")]
+    pub of_lags: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_response: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate: Option<bool>,
+}
+
+// changes the order in place, it keeps its txid and queue priority where possible
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AmendOrder {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CancelOrder {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
+}
+
+// dead man's switch, every open order is cancelled once `timeout` seconds pass without a new call, 0 disables it
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CancelAllOrdersAfter {
+    pub timeout: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AddOrderBatch {
+    pub pair: String,
+    pub orders: Vec<BatchOrder>,
+
+    // optional
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate: Option<bool>,
+}
+
+// an AddOrder without the pair, the batch sets it for every order
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct BatchOrder {
+    pub ordertype: OrderType,
+    #[serde(rename = "type")]
+    pub type_field: Side,
+    pub volume: Decimal,
+
+    // optional
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "userref")]
+    pub user_ref: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "displayvol")]
+    pub display_vol: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price2: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leverage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduce_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "stptype")]
+    pub stp_type: Option<StpType>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "oflags")]
+    pub of_lags: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "timeinforce")]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "starttm")]
+    pub start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "expiretm")]
+    pub expire_time: Option<String>,
+}
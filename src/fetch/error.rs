@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::CreateSignError;
+use crate::{CreateSignError, fetch::limiter::RateLimitError};
 
 #[derive(Debug, Error)]
 pub enum FetchError {
@@ -15,6 +15,23 @@ pub enum FetchError {
 
     #[error(transparent)]
     Parse(#[from] NestedParseError),
+
+    #[error(transparent)]
+    Exchange(#[from] ExchangeError),
+
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitError),
+}
+
+impl FetchError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            FetchError::Exchange(error) => error.is_retryable(),
+            FetchError::RateLimited(_) => true,
+            FetchError::GetHttpRequest(error) => error.is_timeout() || error.is_connect(),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -30,12 +47,90 @@ pub enum AuthFetchError {
 
     #[error(transparent)]
     ParseUrl(#[from] url::ParseError),
-    
+
     #[error(transparent)]
     Parse(#[from] NestedParseError),
 
     #[error(transparent)]
     Auth(#[from] CreateSignError),
+
+    #[error(transparent)]
+    Exchange(#[from] ExchangeError),
+
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitError),
+}
+
+impl AuthFetchError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AuthFetchError::Exchange(error) => error.is_retryable(),
+            AuthFetchError::RateLimited(_) => true,
+            AuthFetchError::GetHttpRequest(error) => error.is_timeout() || error.is_connect(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorCategory {
+    General,
+    Api,
+    Query,
+    Order,
+    Trade,
+    Funding,
+    Service,
+    Session,
+    Unknown,
+}
+
+// kraken errors look like `EOrder:Insufficient funds`, `category` and `message` describe the first one
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Kraken error: {}", .errors.join(", "))]
+pub struct ExchangeError {
+    pub category: ErrorCategory,
+    pub message: String,
+    pub errors: Vec<String>,
+}
+
+impl ExchangeError {
+    // warnings start with a `W` and do not fail the call
+    pub fn from_errors(errors: &[String]) -> Option<Self> {
+        let errors: Vec<String> = errors.iter().filter(|e| !e.starts_with('W')).cloned().collect();
+        let (category, message) = errors.first()?.split_once(':').unwrap_or((&errors[0], ""));
+
+        let category = match category {
+            "EGeneral" => ErrorCategory::General,
+            "EAPI" => ErrorCategory::Api,
+            "EQuery" => ErrorCategory::Query,
+            "EOrder" => ErrorCategory::Order,
+            "ETrade" => ErrorCategory::Trade,
+            "EFunding" => ErrorCategory::Funding,
+            "EService" => ErrorCategory::Service,
+            "ESession" => ErrorCategory::Session,
+            _ => ErrorCategory::Unknown,
+        };
+
+        Some(Self {
+            category,
+            message: message.to_string(),
+            errors,
+        })
+    }
+
+    // the request was fine, trying again after a pause can succeed
+    pub fn is_retryable(&self) -> bool {
+        let message = self.message.as_str();
+
+        match self.category {
+            ErrorCategory::Service => true,
+            ErrorCategory::Api => message.starts_with("Rate limit exceeded") || message.starts_with("Invalid nonce"),
+            ErrorCategory::Order => message.starts_with("Rate limit exceeded"),
+            ErrorCategory::General => message.starts_with("Temporary lockout") || message.starts_with("Internal error"),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -1,11 +1,7 @@
-use std::{
-    collections::HashMap,
-    env,
-    os::raw,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::{collections::HashMap, env, os::raw, sync::Arc};
 
 use dotenv::dotenv;
+use futures_util::{Stream, TryStreamExt, stream};
 use reqwest::{Client, header::HeaderMap};
 use serde::{
     Deserialize, Serialize,
@@ -16,16 +12,29 @@ use thiserror::Error;
 use url::{ParseError, Url};
 
 use crate::{
-    AddOrder, BalanceType, CreateSignError,
+    AddOrder, BalanceType, CreateSignError, GetWebSocketError, WebSocketKey,
+    auth::nonce::NonceSource,
     fetch::{
-        body::TradeHistoryBody,
-        error::{AuthFetchError, FetchError, KrakenEnvError, NestedParseError},
-        types::{AssetPairs, Balance, BalanceEx, BalanceTrade, KraRre, RawCandleStick, ServerTime, Trade},
+        body::{
+            AddOrderBatch, AmendOrder, CancelAllOrdersAfter, CancelOrder, ClosedOrdersBody, EditOrder, LedgersBody, OpenOrdersBody, QueryLedgersBody,
+            QueryOrdersBody, TradeHistoryBody,
+        },
+        error::{AuthFetchError, ExchangeError, FetchError, KrakenEnvError, NestedParseError},
+        limiter::{Cost, OrderChange, RateLimitError, RateLimiter},
+        types::{
+            AddOrderBatchResult, AddOrderResult, AmendOrderResult, AssetInfo, AssetNames, AssetPairs, Balance, BalanceEx, BalanceTrade,
+            CancelAllAfterResult, CancelResult, Depth, EditOrderResult, HistoryPage, KraRre, LedgerEntry, Order, PublicTrade, PublicTrades,
+            RawCandleStick, ServerTime, SpreadLevel, Spreads, SystemStatus, TickerInfo, Trade,
+        },
         urls::{
-            ADD_ORDER_URL, ASSET_PAIRS_URL, BALANCE_EX_URL, BALANCE_TRADE_URL, BALANCE_URL, BASE_URL, OHLC_URL, SERVER_TIME_URL, TRADES_HISTORY_URL,
+            ADD_ORDER_BATCH_URL, ADD_ORDER_URL, AMEND_ORDER_URL, ASSET_PAIRS_URL, ASSETS_URL, BALANCE_EX_URL, BALANCE_TRADE_URL, BALANCE_URL,
+            CANCEL_ALL_AFTER_URL, CANCEL_ALL_URL, CANCEL_ORDER_URL, CLOSED_ORDERS_URL, DEPTH_URL, EDIT_ORDER_URL, Endpoints, LEDGERS_URL, OHLC_URL,
+            OPEN_ORDERS_URL, QUERY_LEDGERS_URL, QUERY_ORDERS_URL, SERVER_TIME_URL, SPREAD_URL, SYSTEM_STATUS_URL, TICKER_URL, TRADES_HISTORY_URL,
+            TRADES_URL,
         },
     },
-    get_kraken_signature, pp_json,
+    get_kraken_signature, get_websocket_token, pp_json,
+    types::decimal::Decimal,
 };
 
 #[derive(Debug, Clone)]
@@ -33,6 +42,9 @@ pub struct Kraken {
     public_key: String,
     private_key: String,
     client: Client,
+    endpoints: Endpoints,
+    limiter: Arc<RateLimiter>,
+    nonces: Arc<NonceSource>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -53,6 +65,9 @@ impl Kraken {
             public_key,
             private_key,
             client: Client::new(),
+            endpoints: Endpoints::from_env(),
+            limiter: Arc::new(RateLimiter::default()),
+            nonces: Arc::new(NonceSource::default()),
         })
     }
 
@@ -61,27 +76,55 @@ impl Kraken {
             public_key: public_key.into(),
             private_key: private_key.into(),
             client: Client::new(),
+            endpoints: Endpoints::default(),
+            limiter: Arc::new(RateLimiter::default()),
+            nonces: Arc::new(NonceSource::default()),
         }
     }
 
+    pub fn endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    pub fn get_endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    // clones made after this share the limiter, so they spend from the same counters
+    pub fn rate_limit(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = Arc::new(limiter);
+        self
+    }
+
+    pub fn get_rate_limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    // like the limiter, the nonce source is shared with every later clone
+    pub fn nonce_source(mut self, nonces: NonceSource) -> Self {
+        self.nonces = Arc::new(nonces);
+        self
+    }
+
+    pub fn get_nonce_source(&self) -> &NonceSource {
+        &self.nonces
+    }
+
     pub fn nested<T: DeserializeOwned>(path: &str, json: &mut Value) -> Result<T, NestedParseError> {
         let value = json.pointer_mut(path).ok_or(NestedParseError::MissingField(path.to_string()))?;
         let asset: T = serde_json::from_value(value.take())?;
         Ok(asset)
     }
 
-    fn get_nonce() -> u128 {
-        SystemTime::now().duration_since(UNIX_EPOCH).expect("Unable to get time").as_millis()
-    }
-
-    fn build_url(end_point: &str) -> Result<Url, ParseError> {
-        let url = format!("{}{}", BASE_URL, end_point);
-        return Url::parse(&url);
+    fn build_url(&self, end_point: &str) -> Result<Url, ParseError> {
+        let url = format!("{}{}", self.endpoints.rest, end_point);
+        Url::parse(&url)
     }
 
-    fn build_params_url(end_point: &str, params: Vec<(&str, &str)>) -> Result<Url, ParseError> {
-        let url = format!("{}{}", BASE_URL, end_point);
-        return Url::parse_with_params(&url, params);
+    fn build_params_url(&self, end_point: &str, params: Vec<(&str, &str)>) -> Result<Url, ParseError> {
+        let url = format!("{}{}", self.endpoints.rest, end_point);
+        Url::parse_with_params(&url, params)
     }
 
     fn auth_headers<T: Serialize>(&self, url: &str, body: &AuthBody<T>) -> Result<HeaderMap, CreateSignError> {
@@ -94,21 +137,22 @@ impl Kraken {
         Ok(headers)
     }
 
-    fn body_to_auth<T: Serialize>(body: T) -> AuthBody<T> {
+    fn body_to_auth<T: Serialize>(&self, body: T) -> AuthBody<T> {
         AuthBody {
-            nonce: Self::get_nonce(),
+            nonce: self.nonces.next(),
             body,
         }
     }
 
     pub async fn get_server_time(&self) -> Result<ServerTime, FetchError> {
-        let res: ServerTime = Client::new().post(SERVER_TIME_URL).send().await?.json().await?;
-        Ok(res)
+        let url = self.build_url(SERVER_TIME_URL)?;
+        let mut res = self.public_request(url).await?;
+        Ok(Self::nested("", &mut res.result)?)
     }
 
     pub async fn get_asset_pair(&self, pair: &str) -> Result<AssetPairs, FetchError> {
-        let url = Self::build_params_url(ASSET_PAIRS_URL, vec![("pair", pair)])?;
-        let mut res: KraRre<Value> = self.client.post(url).send().await?.json().await?;
+        let url = self.build_params_url(ASSET_PAIRS_URL, vec![("pair", pair)])?;
+        let mut res = self.public_request(url).await?;
 
         let path = format!("/{}", pair.replace('/', "~1"));
         let assets: AssetPairs = Self::nested(&path, &mut res.result)?;
@@ -118,9 +162,9 @@ impl Kraken {
 
     pub async fn get_ohlc(&self, pair: &str, interval: &str, since: &str) -> Result<Vec<RawCandleStick>, FetchError> {
         let params = vec![("pair", pair), ("interval", interval), ("since", since)];
-        let url = Self::build_params_url(OHLC_URL, params)?;
+        let url = self.build_params_url(OHLC_URL, params)?;
 
-        let mut res: KraRre<Value> = self.client.post(url).send().await?.json().await?;
+        let mut res = self.public_request(url).await?;
 
         let path = format!("/{}", pair.replace('/', "~1"));
         let raw_sticks: Vec<RawCandleStick> = Self::nested(&path, &mut res.result)?;
@@ -128,83 +172,372 @@ impl Kraken {
         Ok(raw_sticks)
     }
 
-    pub async fn get_balance(&self, multiplier: BalanceType) -> Result<KraRre<Balance>, AuthFetchError> {
-        let extra = ("rebase_multiplier", multiplier.to_string());
-        let body = Self::body_to_auth(HashMap::from([extra]));
+    // `since` is a nanosecond cursor, at most 1000 trades after it are returned
+    pub async fn get_trades(&self, pair: &str, since: &str) -> Result<PublicTrades, FetchError> {
+        let params = vec![("pair", pair), ("since", since)];
+        let url = self.build_params_url(TRADES_URL, params)?;
+
+        let mut res = self.public_request(url).await?;
+
+        let path = format!("/{}", pair.replace('/', "~1"));
+        let trades: Vec<PublicTrade> = Self::nested(&path, &mut res.result)?;
+        let last: String = Self::nested("/last", &mut res.result)?;
+
+        Ok(PublicTrades {
+            trades,
+            last,
+        })
+    }
+
+    // keyed by the pair names kraken answers with
+    pub async fn get_ticker(&self, pairs: &[&str]) -> Result<HashMap<String, TickerInfo>, FetchError> {
+        let pairs = pairs.join(",");
+        let url = self.build_params_url(TICKER_URL, vec![("pair", &pairs)])?;
+
+        let mut res = self.public_request(url).await?;
+        let tickers: HashMap<String, TickerInfo> = Self::nested("", &mut res.result)?;
+
+        Ok(tickers)
+    }
+
+    // `count` limits the levels per side, kraken allows up to 500
+    pub async fn get_depth(&self, pair: &str, count: Option<u32>) -> Result<Depth, FetchError> {
+        let count = count.map(|c| c.to_string());
+        let mut params = vec![("pair", pair)];
+        if let Some(count) = count.as_deref() {
+            params.push(("count", count));
+        }
+
+        let url = self.build_params_url(DEPTH_URL, params)?;
+        let mut res = self.public_request(url).await?;
+
+        let path = format!("/{}", pair.replace('/', "~1"));
+        let depth: Depth = Self::nested(&path, &mut res.result)?;
+
+        Ok(depth)
+    }
 
-        let url = Self::build_url(BALANCE_URL)?;
-        let headers = self.auth_headers(BALANCE_URL, &body)?;
+    pub async fn get_spread(&self, pair: &str, since: &str) -> Result<Spreads, FetchError> {
+        let params = vec![("pair", pair), ("since", since)];
+        let url = self.build_params_url(SPREAD_URL, params)?;
 
-        let mut res: KraRre<Value> = Client::new().post(url).headers(headers).json(&body).send().await?.json().await?;
-        let assets: f64 = Self::nested(&"/ZEUR", &mut res.result)?;
+        let mut res = self.public_request(url).await?;
+
+        let path = format!("/{}", pair.replace('/', "~1"));
+        let spreads: Vec<SpreadLevel> = Self::nested(&path, &mut res.result)?;
+        let last: i64 = Self::nested("/last", &mut res.result)?;
+
+        Ok(Spreads {
+            spreads,
+            last,
+        })
+    }
+
+    // an empty slice returns every asset
+    pub async fn get_assets(&self, assets: &[&str]) -> Result<HashMap<String, AssetInfo>, FetchError> {
+        let assets = assets.join(",");
+        let url = match assets.is_empty() {
+            true => self.build_url(ASSETS_URL)?,
+            false => self.build_params_url(ASSETS_URL, vec![("asset", &assets)])?,
+        };
+
+        let mut res = self.public_request(url).await?;
+        let assets: HashMap<String, AssetInfo> = Self::nested("", &mut res.result)?;
+
+        Ok(assets)
+    }
+
+    pub async fn get_system_status(&self) -> Result<SystemStatus, FetchError> {
+        let url = self.build_url(SYSTEM_STATUS_URL)?;
+        let mut res = self.public_request(url).await?;
+        Ok(Self::nested("", &mut res.result)?)
+    }
+
+    // /0/public/Assets, used to turn kraken's asset codes into the names the socket uses
+    pub async fn get_asset_names(&self) -> Result<AssetNames, FetchError> {
+        Ok(AssetNames::new(self.get_assets(&[]).await?))
+    }
+
+    // keyed by kraken's asset code (XXBT, ZEUR), `get_balance_normalized` keys it by the names the socket uses
+    pub async fn get_balance(&self, multiplier: BalanceType) -> Result<KraRre<HashMap<String, Balance>>, AuthFetchError> {
+        let extra = ("rebase_multiplier", multiplier.to_string());
+        let mut res: KraRre<Value> = self.private_request(BALANCE_URL, HashMap::from([extra])).await?;
+        let assets: HashMap<String, Decimal> = Self::nested("", &mut res.result)?;
+
+        let balances = assets.into_iter().map(|(asset, balance)| (asset, Balance { balance })).collect();
 
         Ok(KraRre {
             error: res.error,
-            result: Balance {
-                balance: assets,
-            },
+            result: balances,
         })
     }
 
-    pub async fn get_balance_ex(&self, multiplier: BalanceType) -> Result<KraRre<BalanceEx>, AuthFetchError> {
+    // keyed by kraken's asset code (XXBT, ZEUR), `get_balance_ex_normalized` keys it by the names the socket uses
+    pub async fn get_balance_ex(&self, multiplier: BalanceType) -> Result<KraRre<HashMap<String, BalanceEx>>, AuthFetchError> {
         let extra = ("rebase_multiplier", multiplier.to_string());
-        let body = Self::body_to_auth(HashMap::from([extra]));
+        self.private_request(BALANCE_EX_URL, HashMap::from([extra])).await
+    }
 
-        let url = Self::build_url(BALANCE_EX_URL)?;
-        let headers = self.auth_headers(BALANCE_EX_URL, &body)?;
+    // keyed by BTC and EUR, `names` comes from `get_asset_names` and is worth keeping around between calls
+    pub async fn get_balance_normalized(
+        &self,
+        multiplier: BalanceType,
+        names: &AssetNames,
+    ) -> Result<KraRre<HashMap<String, Balance>>, AuthFetchError> {
+        let res = self.get_balance(multiplier).await?;
 
-        let mut res: KraRre<Value> = Client::new().post(url).headers(headers).json(&body).send().await?.json().await?;
+        Ok(KraRre {
+            error: res.error,
+            result: names.normalize_keys(res.result),
+        })
+    }
 
-        let assets: BalanceEx = Self::nested(&"/ZEUR", &mut res.result)?;
+    pub async fn get_balance_ex_normalized(
+        &self,
+        multiplier: BalanceType,
+        names: &AssetNames,
+    ) -> Result<KraRre<HashMap<String, BalanceEx>>, AuthFetchError> {
+        let res = self.get_balance_ex(multiplier).await?;
 
         Ok(KraRre {
             error: res.error,
-            result: assets,
+            result: names.normalize_keys(res.result),
         })
     }
 
     pub async fn get_balance_trade(&self, multiplier: BalanceType, asset: &str) -> Result<KraRre<BalanceTrade>, AuthFetchError> {
         let extra = [("rebase_multiplier", multiplier.to_string()), ("asset", asset.to_string())];
-        let body = Self::body_to_auth(HashMap::from(extra));
+        self.private_request(BALANCE_TRADE_URL, HashMap::from(extra)).await
+    }
+
+    // a single page of up to 50 trades, newest first
+    pub async fn get_trades_history(&self, params: &TradeHistoryBody) -> Result<Vec<Trade>, AuthFetchError> {
+        Ok(self.get_trades_history_page(params).await?.items)
+    }
+
+    pub async fn get_trades_history_page(&self, params: &TradeHistoryBody) -> Result<HistoryPage<Trade>, AuthFetchError> {
+        self.history_page(TRADES_HISTORY_URL, params, "/trades").await
+    }
+
+    // pages through every trade between `start` and `end`, starting at `ofs`
+    pub fn trades_history_pages(&self, params: &TradeHistoryBody) -> impl Stream<Item = Result<Vec<Trade>, AuthFetchError>> + '_ {
+        self.history_pages(TRADES_HISTORY_URL, params.clone(), params.ofs, "/trades", |body, ofs| {
+            body.ofs = Some(ofs)
+        })
+    }
+
+    pub async fn get_all_trades_history(&self, params: &TradeHistoryBody) -> Result<Vec<Trade>, AuthFetchError> {
+        self.trades_history_pages(params).try_concat().await
+    }
+
+    pub async fn get_ledgers(&self, params: &LedgersBody) -> Result<HistoryPage<LedgerEntry>, AuthFetchError> {
+        self.history_page(LEDGERS_URL, params, "/ledger").await
+    }
+
+    pub fn ledgers_pages(&self, params: &LedgersBody) -> impl Stream<Item = Result<Vec<LedgerEntry>, AuthFetchError>> + '_ {
+        self.history_pages(LEDGERS_URL, params.clone(), params.ofs, "/ledger", |body, ofs| body.ofs = Some(ofs))
+    }
+
+    pub async fn get_all_ledgers(&self, params: &LedgersBody) -> Result<Vec<LedgerEntry>, AuthFetchError> {
+        self.ledgers_pages(params).try_concat().await
+    }
+
+    pub async fn get_query_ledgers(&self, params: &QueryLedgersBody) -> Result<Vec<LedgerEntry>, AuthFetchError> {
+        let res: KraRre<Value> = self.private_request(QUERY_LEDGERS_URL, params).await?;
+        Ok(Self::keyed(res.result)?)
+    }
+
+    pub async fn get_open_orders(&self, params: &OpenOrdersBody) -> Result<Vec<Order>, AuthFetchError> {
+        let mut res: KraRre<Value> = self.private_request(OPEN_ORDERS_URL, params).await?;
+        let raw_orders: Value = Self::nested("/open", &mut res.result)?;
+
+        Ok(Self::keyed(raw_orders)?)
+    }
+
+    pub async fn get_closed_orders(&self, params: &ClosedOrdersBody) -> Result<Vec<Order>, AuthFetchError> {
+        let mut res: KraRre<Value> = self.private_request(CLOSED_ORDERS_URL, params).await?;
+        let raw_orders: Value = Self::nested("/closed", &mut res.result)?;
 
-        let url = Self::build_url(BALANCE_TRADE_URL)?;
-        let headers = self.auth_headers(BALANCE_TRADE_URL, &body)?;
+        Ok(Self::keyed(raw_orders)?)
+    }
+
+    pub async fn get_query_orders(&self, params: &QueryOrdersBody) -> Result<Vec<Order>, AuthFetchError> {
+        let res: KraRre<Value> = self.private_request(QUERY_ORDERS_URL, params).await?;
+        Ok(Self::keyed(res.result)?)
+    }
 
-        let mut res: KraRre<BalanceTrade> = Client::new().post(url).headers(headers).json(&body).send().await?.json().await?;
+    pub async fn post_add_order(&self, body: &AddOrder) -> Result<KraRre<AddOrderResult>, AuthFetchError> {
+        self.acquire_order(&body.pair, 1.).await?;
+        let res: KraRre<AddOrderResult> = self.private_request(ADD_ORDER_URL, body).await?;
+
+        for txid in res.result.txid.iter() {
+            self.limiter.track_order(&body.pair, txid);
+        }
 
         Ok(res)
     }
 
-    pub async fn get_trades_history(&self, params: &TradeHistoryBody) -> Result<Vec<Trade>, AuthFetchError> {
-        let body = Self::body_to_auth(params);
+    pub async fn post_add_order_batch(&self, body: &AddOrderBatch) -> Result<KraRre<AddOrderBatchResult>, AuthFetchError> {
+        self.acquire_order(&body.pair, body.orders.len() as f64).await?;
+        let res: KraRre<AddOrderBatchResult> = self.private_request(ADD_ORDER_BATCH_URL, body).await?;
 
-        let url = Self::build_url(TRADES_HISTORY_URL)?;
-        let headers = self.auth_headers(TRADES_HISTORY_URL, &body)?;
+        for txid in res.result.orders.iter().filter_map(|o| o.txid.as_ref()) {
+            self.limiter.track_order(&body.pair, txid);
+        }
 
-        let mut res: KraRre<Value> = Client::new().post(url).headers(headers).json(&body).send().await?.json().await?;
-        let mut raw_trades: Value = Self::nested(&"/trades", &mut res.result)?;
+        Ok(res)
+    }
 
-        let mut obj_trades = raw_trades.as_object_mut().ok_or(serde_json::Error::custom("Unable to parse trades"))?;
+    pub async fn post_edit_order(&self, body: &EditOrder) -> Result<KraRre<EditOrderResult>, AuthFetchError> {
+        self.acquire_change(Some(&body.txid), OrderChange::Edit).await?;
+        let res: KraRre<EditOrderResult> = self.private_request(EDIT_ORDER_URL, body).await?;
 
-        let trades = obj_trades
-            .iter_mut()
-            .map(|(key, value)| {
-                value["id"] = json!(key);
-                serde_json::from_value(value.take())
-            })
-            .collect::<Result<Vec<Trade>, _>>()?;
+        self.limiter.track_order(&body.pair, &res.result.txid);
 
-        Ok(trades)
+        Ok(res)
     }
 
-    pub async fn post_add_order(&self, body: &AddOrder) -> Result<KraRre<Value>, AuthFetchError> {
-        let body = Self::body_to_auth(body);
+    pub async fn post_amend_order(&self, body: &AmendOrder) -> Result<KraRre<AmendOrderResult>, AuthFetchError> {
+        self.acquire_change(body.txid.as_deref(), OrderChange::Amend).await?;
+        self.private_request(AMEND_ORDER_URL, body).await
+    }
 
-        let url = Self::build_url(ADD_ORDER_URL)?;
-        let headers = self.auth_headers(ADD_ORDER_URL, &body)?;
+    pub async fn post_cancel_order(&self, body: &CancelOrder) -> Result<KraRre<CancelResult>, AuthFetchError> {
+        self.acquire_change(body.txid.as_deref(), OrderChange::Cancel).await?;
+        self.private_request(CANCEL_ORDER_URL, body).await
+    }
 
-        let res: KraRre<Value> = Client::new().post(url).headers(headers).json(&body).send().await?.json().await?;
+    pub async fn post_cancel_all(&self) -> Result<KraRre<CancelResult>, AuthFetchError> {
+        let res = self.private_request(CANCEL_ALL_URL, HashMap::<&str, String>::new()).await?;
+        self.limiter.forget_orders();
 
         Ok(res)
     }
+
+    pub async fn post_cancel_all_orders_after(&self, body: &CancelAllOrdersAfter) -> Result<KraRre<CancelAllAfterResult>, AuthFetchError> {
+        self.private_request(CANCEL_ALL_AFTER_URL, body).await
+    }
+
+    pub async fn get_websocket_token(&self) -> Result<WebSocketKey, GetWebSocketError> {
+        self.limiter.acquire(Cost::Private(1.)).await?;
+        get_websocket_token(&self.client, &self.endpoints.rest, &self.public_key, &self.private_key, &self.nonces).await
+    }
+
+    async fn public_request(&self, url: Url) -> Result<KraRre<Value>, FetchError> {
+        self.limiter.acquire(Cost::Public).await?;
+
+        let res: KraRre<Value> = self.client.post(url).send().await?.json().await?;
+        Ok(Self::check(res)?)
+    }
+
+    // signs the body and posts it to a private endpoint
+    async fn private_request<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: B) -> Result<KraRre<T>, AuthFetchError> {
+        let cost = Self::private_cost(path);
+        if cost > 0. {
+            self.limiter.acquire(Cost::Private(cost)).await?;
+        }
+
+        let body = self.body_to_auth(body);
+
+        let url = self.build_url(path)?;
+        let headers = self.auth_headers(path, &body)?;
+
+        let res: KraRre<Value> = Client::new().post(url).headers(headers).json(&body).send().await?.json().await?;
+        let mut res = Self::check(res)?;
+
+        Ok(KraRre {
+            result: Self::nested("", &mut res.result)?,
+            error: res.error,
+        })
+    }
+
+    async fn history_page<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: B, key: &str) -> Result<HistoryPage<T>, AuthFetchError> {
+        let mut res: KraRre<Value> = self.private_request(path, body).await?;
+        let raw_items: Value = Self::nested(key, &mut res.result)?;
+
+        Ok(HistoryPage {
+            items: Self::keyed(raw_items)?,
+            count: res.result["count"].as_i64(),
+        })
+    }
+
+    // walks `ofs` forward until a page comes back empty or `count` items were seen
+    fn history_pages<'a, B: Serialize + 'a, T: DeserializeOwned + 'a>(
+        &'a self,
+        path: &'static str,
+        body: B,
+        ofs: Option<i64>,
+        key: &'static str,
+        set_ofs: fn(&mut B, i64),
+    ) -> impl Stream<Item = Result<Vec<T>, AuthFetchError>> + 'a {
+        let start = Some((body, ofs.unwrap_or(0)));
+
+        stream::try_unfold(start, move |state| async move {
+            let Some((mut body, ofs)) = state else {
+                return Ok(None);
+            };
+
+            set_ofs(&mut body, ofs);
+            let page: HistoryPage<T> = self.history_page(path, &body, key).await?;
+
+            if page.items.is_empty() {
+                return Ok(None);
+            }
+
+            let ofs = ofs + page.items.len() as i64;
+            let next = match page.count {
+                Some(count) if ofs >= count => None,
+                _ => Some((body, ofs)),
+            };
+
+            Ok(Some((page.items, next)))
+        })
+    }
+
+    // history calls count double, order calls are limited per pair instead of on the api counter
+    fn private_cost(path: &str) -> f64 {
+        match path {
+            TRADES_HISTORY_URL | LEDGERS_URL | QUERY_LEDGERS_URL => 2.,
+            ADD_ORDER_URL | ADD_ORDER_BATCH_URL | EDIT_ORDER_URL | AMEND_ORDER_URL | CANCEL_ORDER_URL | CANCEL_ALL_URL | CANCEL_ALL_AFTER_URL => 0.,
+            _ => 1.,
+        }
+    }
+
+    pub(crate) async fn acquire_order(&self, pair: &str, cost: f64) -> Result<(), RateLimitError> {
+        let cost = Cost::Order {
+            pair: pair.to_string(),
+            cost,
+        };
+
+        self.limiter.acquire(cost).await
+    }
+
+    // orders placed elsewhere have an unknown age and are not charged
+    async fn acquire_change(&self, txid: Option<&str>, change: OrderChange) -> Result<(), RateLimitError> {
+        match txid.and_then(|txid| self.limiter.change_cost(txid, change)) {
+            Some(cost) => self.limiter.acquire(cost).await,
+            None => Ok(()),
+        }
+    }
+
+    // kraken answers 200 with an error list, any error in it fails the call before `result` is looked at
+    fn check(res: KraRre<Value>) -> Result<KraRre<Value>, ExchangeError> {
+        match ExchangeError::from_errors(&res.error) {
+            Some(error) => Err(error),
+            None => Ok(res),
+        }
+    }
+
+    // kraken keys trades and orders by their id, the key ends up in the `id` field
+    fn keyed<T: DeserializeOwned>(mut raw: Value) -> Result<Vec<T>, serde_json::Error> {
+        let object = raw.as_object_mut().ok_or(serde_json::Error::custom("Unable to parse keyed object"))?;
+
+        object
+            .iter_mut()
+            .map(|(key, value)| {
+                value["id"] = json!(key);
+                serde_json::from_value(value.take())
+            })
+            .collect()
+    }
 }
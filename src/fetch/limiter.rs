@@ -0,0 +1,364 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use thiserror::Error;
+// tokio's clock, so tests can pause and advance it
+use tokio::time::Instant;
+
+#[derive(Debug, Error)]
+pub enum RateLimitError {
+    #[error("Rate limit reached, retry after {retry_after:?}")]
+    Limited { retry_after: Duration },
+
+    // waiting would never help, the counter tops out below the cost
+    #[error("Call costs {cost} but the counter max is {max}")]
+    TooCostly { cost: f64, max: f64 },
+}
+
+// kraken verification tier, decides how fast the counters decay
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Tier {
+    #[default]
+    Starter,
+    Intermediate,
+    Pro,
+}
+
+impl Tier {
+    // max and decay per second of the private api counter
+    fn api_limit(&self) -> (f64, f64) {
+        match self {
+            Tier::Starter => (15., 0.33),
+            Tier::Intermediate => (20., 0.5),
+            Tier::Pro => (20., 1.),
+        }
+    }
+
+    // max and decay per second of the per pair order counter
+    fn order_limit(&self) -> (f64, f64) {
+        match self {
+            Tier::Starter => (60., 1.),
+            Tier::Intermediate => (125., 2.34),
+            Tier::Pro => (180., 3.75),
+        }
+    }
+}
+
+// past this age cancels and edits cost nothing extra, see OrderChange::penalty
+const PENALTY_WINDOW: Duration = Duration::from_secs(300);
+
+// public calls are limited per ip, roughly one a second with a small burst
+const PUBLIC_LIMIT: (f64, f64) = (5., 1.);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum RatePolicy {
+    // sleep until the call fits
+    #[default]
+    Wait,
+    // return a RateLimitError right away
+    FailFast,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cost {
+    Public,
+    Private(f64),
+    Order { pair: String, cost: f64 },
+}
+
+// changes to a resting order cost more the younger the order is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderChange {
+    Cancel,
+    Edit,
+    Amend,
+}
+
+impl OrderChange {
+    fn penalty(&self, age: Duration) -> f64 {
+        let table: &[(u64, f64)] = match self {
+            OrderChange::Cancel => &[(5, 8.), (10, 6.), (15, 5.), (45, 4.), (90, 2.), (300, 1.)],
+            OrderChange::Edit => &[(5, 6.), (10, 5.), (15, 4.), (45, 2.), (90, 1.)],
+            OrderChange::Amend => &[(5, 3.), (10, 2.), (15, 1.)],
+        };
+
+        table
+            .iter()
+            .find(|(secs, _)| age < Duration::from_secs(*secs))
+            .map_or(0., |(_, cost)| *cost)
+    }
+}
+
+#[derive(Debug)]
+struct Counter {
+    level: f64,
+    max: f64,
+    decay: f64,
+    updated: Instant,
+}
+
+impl Counter {
+    fn new((max, decay): (f64, f64)) -> Self {
+        Self {
+            level: 0.,
+            max,
+            decay,
+            updated: Instant::now(),
+        }
+    }
+
+    // time until `cost` fits under the max, zero when it can be spent now
+    fn wait_for(&mut self, cost: f64) -> Duration {
+        let now = Instant::now();
+        self.level = (self.level - now.duration_since(self.updated).as_secs_f64() * self.decay).max(0.);
+        self.updated = now;
+
+        let over = self.level + cost - self.max;
+        match over > 0. {
+            true => Duration::from_secs_f64(over / self.decay),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    api: Counter,
+    public: Counter,
+    orders: HashMap<String, Counter>,
+    // txid -> pair and placement time, used to price cancels and edits
+    placed: HashMap<String, (String, Instant)>,
+}
+
+// shared by every clone of a Kraken client, mirrors the counters kraken keeps for the key
+#[derive(Debug)]
+pub struct RateLimiter {
+    tier: Tier,
+    policy: RatePolicy,
+    enabled: bool,
+    state: Mutex<LimiterState>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(Tier::default(), RatePolicy::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(tier: Tier, policy: RatePolicy) -> Self {
+        let state = LimiterState {
+            api: Counter::new(tier.api_limit()),
+            public: Counter::new(PUBLIC_LIMIT),
+            orders: HashMap::new(),
+            placed: HashMap::new(),
+        };
+
+        Self {
+            tier,
+            policy,
+            enabled: true,
+            state: Mutex::new(state),
+        }
+    }
+
+    // never throttles, for local mocks
+    pub fn unlimited() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn tier(&self) -> Tier {
+        self.tier
+    }
+
+    pub fn policy(&self) -> RatePolicy {
+        self.policy
+    }
+
+    pub async fn acquire(&self, cost: Cost) -> Result<(), RateLimitError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let tier = self.tier;
+
+                let (counter, amount) = match &cost {
+                    Cost::Public => (&mut state.public, 1.),
+                    Cost::Private(amount) => (&mut state.api, *amount),
+                    Cost::Order { pair, cost } => {
+                        let counter = state.orders.entry(pair.clone()).or_insert_with(|| Counter::new(tier.order_limit()));
+                        (counter, *cost)
+                    }
+                };
+
+                if amount > counter.max {
+                    return Err(RateLimitError::TooCostly {
+                        cost: amount,
+                        max: counter.max,
+                    });
+                }
+
+                let wait = counter.wait_for(amount);
+                if wait.is_zero() {
+                    counter.level += amount;
+                    return Ok(());
+                }
+                wait
+            };
+
+            match self.policy {
+                RatePolicy::Wait => tokio::time::sleep(wait).await,
+                RatePolicy::FailFast => {
+                    return Err(RateLimitError::Limited {
+                        retry_after: wait,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn track_order(&self, pair: &str, txid: &str) {
+        let mut state = self.state.lock().unwrap();
+
+        // filled and expired orders are never cancelled, drop them once they no longer carry a penalty
+        state.placed.retain(|_, (_, placed)| placed.elapsed() < PENALTY_WINDOW);
+        state.placed.insert(txid.to_string(), (pair.to_string(), Instant::now()));
+    }
+
+    // None for orders this client did not place, or placed so long ago the change is free
+    pub fn change_cost(&self, txid: &str, change: OrderChange) -> Option<Cost> {
+        let mut state = self.state.lock().unwrap();
+
+        // an edit replaces the order with a new txid, an amend keeps it
+        let (pair, placed) = match change {
+            OrderChange::Cancel | OrderChange::Edit => state.placed.remove(txid)?,
+            OrderChange::Amend => state.placed.get(txid).cloned()?,
+        };
+
+        Some(Cost::Order {
+            pair,
+            cost: change.penalty(placed.elapsed()),
+        })
+    }
+
+    pub fn forget_orders(&self) {
+        self.state.lock().unwrap().placed.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::advance;
+
+    use super::*;
+
+    fn order(cost: f64) -> Cost {
+        Cost::Order {
+            pair: "BTC/EUR".into(),
+            cost,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn counter_decays_over_time() {
+        let limiter = RateLimiter::new(Tier::Pro, RatePolicy::FailFast);
+        limiter.acquire(Cost::Private(20.)).await.unwrap();
+
+        // pro decays one a second
+        let Err(RateLimitError::Limited {
+            retry_after,
+        }) = limiter.acquire(Cost::Private(2.)).await
+        else {
+            panic!("a full counter let a call through");
+        };
+        assert_eq!(retry_after, Duration::from_secs(2));
+
+        advance(Duration::from_secs(1)).await;
+        limiter.acquire(Cost::Private(1.)).await.unwrap();
+        assert!(limiter.acquire(Cost::Private(1.)).await.is_err());
+
+        advance(Duration::from_secs(20)).await;
+        limiter.acquire(Cost::Private(20.)).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tiers_set_the_counter_max() {
+        for (tier, api, orders) in [(Tier::Starter, 15., 60.), (Tier::Intermediate, 20., 125.), (Tier::Pro, 20., 180.)] {
+            let limiter = RateLimiter::new(tier, RatePolicy::FailFast);
+
+            assert!(matches!(limiter.acquire(Cost::Private(api + 1.)).await, Err(RateLimitError::TooCostly { max, .. }) if max == api));
+            assert!(matches!(limiter.acquire(order(orders + 1.)).await, Err(RateLimitError::TooCostly { max, .. }) if max == orders));
+
+            limiter.acquire(Cost::Private(api)).await.unwrap();
+            limiter.acquire(order(orders)).await.unwrap();
+
+            // every pair has a counter of its own
+            limiter
+                .acquire(Cost::Order {
+                    pair: "ETH/EUR".into(),
+                    cost: orders,
+                })
+                .await
+                .unwrap();
+            assert!(limiter.acquire(order(1.)).await.is_err());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wait_sleeps_where_fail_fast_errors() {
+        let fail_fast = RateLimiter::new(Tier::Starter, RatePolicy::FailFast);
+        let wait = RateLimiter::new(Tier::Starter, RatePolicy::Wait);
+
+        for limiter in [&fail_fast, &wait] {
+            for _ in 0..5 {
+                limiter.acquire(Cost::Public).await.unwrap();
+            }
+        }
+
+        assert!(matches!(fail_fast.acquire(Cost::Public).await, Err(RateLimitError::Limited { .. })));
+
+        // public calls decay one a second, the paused clock jumps ahead while it sleeps
+        let start = Instant::now();
+        wait.acquire(Cost::Public).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn penalties_drop_with_the_order_age() {
+        let secs = Duration::from_secs;
+
+        let cancel = [(0, 8.), (5, 6.), (14, 5.), (15, 4.), (89, 2.), (299, 1.), (300, 0.)];
+        for (age, cost) in cancel {
+            assert_eq!(OrderChange::Cancel.penalty(secs(age)), cost, "cancel after {age}s");
+        }
+
+        let edit = [(4, 6.), (9, 5.), (10, 4.), (44, 2.), (89, 1.), (90, 0.)];
+        for (age, cost) in edit {
+            assert_eq!(OrderChange::Edit.penalty(secs(age)), cost, "edit after {age}s");
+        }
+
+        let amend = [(0, 3.), (5, 2.), (14, 1.), (15, 0.)];
+        for (age, cost) in amend {
+            assert_eq!(OrderChange::Amend.penalty(secs(age)), cost, "amend after {age}s");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn change_cost_prices_tracked_orders_by_age() {
+        let limiter = RateLimiter::new(Tier::Starter, RatePolicy::FailFast);
+        limiter.track_order("BTC/EUR", "TX1");
+
+        advance(Duration::from_secs(12)).await;
+        assert_eq!(limiter.change_cost("TX1", OrderChange::Amend), Some(order(1.)));
+        // an amend keeps the order, a cancel removes it
+        assert_eq!(limiter.change_cost("TX1", OrderChange::Cancel), Some(order(5.)));
+        assert_eq!(limiter.change_cost("TX1", OrderChange::Cancel), None);
+
+        assert_eq!(limiter.change_cost("unknown", OrderChange::Cancel), None);
+    }
+}
@@ -1,6 +1,6 @@
 pub mod body;
 pub mod error;
 pub mod kraken;
+pub mod limiter;
 pub mod types;
 pub mod urls;
-pub mod utils;
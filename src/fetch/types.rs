@@ -1,18 +1,285 @@
 use core::fmt;
+use std::collections::HashMap;
 
 use nestify::nest;
 use serde::de::value::StrDeserializer;
 use serde::{Deserialize, Deserializer, Serialize, de};
 use serde_json::Value;
 
-use crate::fetch::utils::str_to_f64;
+use crate::{
+    fetch::body::{LedgerType, OrderType, Side},
+    types::decimal::Decimal,
+};
 
 pub type RawCandleStick = (u64, String, String, String, String, String, String, i64);
 
+// one entry of /0/public/Trades, kraken sends it as an array
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublicTrade {
+    pub price: Decimal,
+    pub volume: Decimal,
+    pub time: f64,
+    pub side: String,
+    pub ord_type: String,
+    pub misc: String,
+    #[serde(default)]
+    pub trade_id: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PublicTrades {
+    pub trades: Vec<PublicTrade>,
+    // cursor for the next page, pass it as `since`
+    pub last: String,
+}
+
+// /0/public/Ticker, every pair is a pair of values for today and the last 24 hours
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickerInfo {
+    #[serde(rename = "a")]
+    pub ask: TickerLevel,
+    #[serde(rename = "b")]
+    pub bid: TickerLevel,
+    #[serde(rename = "c")]
+    pub last: (Decimal, Decimal),
+    #[serde(rename = "v")]
+    pub volume: (Decimal, Decimal),
+    #[serde(rename = "p")]
+    pub vwap: (Decimal, Decimal),
+    #[serde(rename = "t")]
+    pub trades: (i64, i64),
+    #[serde(rename = "l")]
+    pub low: (Decimal, Decimal),
+    #[serde(rename = "h")]
+    pub high: (Decimal, Decimal),
+    #[serde(rename = "o")]
+    pub open: Decimal,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickerLevel {
+    pub price: Decimal,
+    pub whole_lot_volume: Decimal,
+    pub lot_volume: Decimal,
+}
+
+// /0/public/Depth
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Depth {
+    pub asks: Vec<DepthLevel>,
+    pub bids: Vec<DepthLevel>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepthLevel {
+    pub price: Decimal,
+    pub volume: Decimal,
+    pub timestamp: i64,
+}
+
+// /0/public/Spread
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpreadLevel {
+    pub time: i64,
+    pub bid: Decimal,
+    pub ask: Decimal,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Spreads {
+    pub spreads: Vec<SpreadLevel>,
+    // cursor for the next call, pass it as `since`
+    pub last: i64,
+}
+
+// /0/public/Assets
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetInfo {
+    pub aclass: String,
+    pub altname: String,
+    pub decimals: i32,
+    pub display_decimals: i32,
+    #[serde(default)]
+    pub collateral_value: Option<Decimal>,
+    #[serde(default)]
+    pub status: String,
+}
+
+// maps kraken's asset codes (XXBT, ZEUR) to the names the socket and the ui use (BTC, EUR)
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct AssetNames {
+    assets: HashMap<String, AssetInfo>,
+}
+
+impl AssetNames {
+    pub fn new(assets: HashMap<String, AssetInfo>) -> Self {
+        Self { assets }
+    }
+
+    pub fn info(&self, asset: &str) -> Option<&AssetInfo> {
+        self.assets.get(asset)
+    }
+
+    // lookup by normalized name, BTC finds the info of XXBT
+    pub fn find(&self, name: &str) -> Option<&AssetInfo> {
+        self.assets.iter().find(|(code, _)| self.normalize(code) == name).map(|(_, info)| info)
+    }
+
+    // staked and earn balances keep their suffix, XXBT.F becomes BTC.F
+    pub fn normalize(&self, asset: &str) -> String {
+        let (code, suffix) = match asset.split_once('.') {
+            Some((code, suffix)) => (code, Some(suffix)),
+            None => (asset, None),
+        };
+
+        let altname = self.assets.get(code).map_or(code, |info| info.altname.as_str());
+        let name = match altname {
+            "XBT" => "BTC",
+            "XDG" => "DOGE",
+            altname => altname,
+        };
+
+        match suffix {
+            Some(suffix) => format!("{}.{}", name, suffix),
+            None => name.to_string(),
+        }
+    }
+
+    pub fn normalize_keys<T>(&self, map: HashMap<String, T>) -> HashMap<String, T> {
+        map.into_iter().map(|(asset, value)| (self.normalize(&asset), value)).collect()
+    }
+}
+
+// /0/public/SystemStatus
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemStatus {
+    pub status: String,
+    pub timestamp: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderDescription {
+    pub order: String,
+    #[serde(default)]
+    pub close: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddOrderResult {
+    pub descr: OrderDescription,
+    pub txid: Vec<String>,
+}
+
+// one entry of an AddOrderBatch, failed orders only carry `error`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchOrderResult {
+    #[serde(default)]
+    pub descr: Option<OrderDescription>,
+    #[serde(default)]
+    pub txid: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddOrderBatchResult {
+    pub orders: Vec<BatchOrderResult>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditOrderResult {
+    pub descr: OrderDescription,
+    pub txid: String,
+    #[serde(rename = "originaltxid")]
+    pub original_txid: String,
+    #[serde(default)]
+    pub volume: Option<Decimal>,
+    #[serde(default)]
+    pub price: Option<Decimal>,
+    #[serde(default)]
+    pub price2: Option<Decimal>,
+    #[serde(default)]
+    pub orders_cancelled: i64,
+    #[serde(default)]
+    pub status: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AmendOrderResult {
+    pub amend_id: String,
+}
+
+// CancelOrder and CancelAll both answer with the number of cancelled orders
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CancelResult {
+    pub count: i64,
+    #[serde(default)]
+    pub pending: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelAllAfterResult {
+    pub current_time: String,
+    pub trigger_time: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderDetails {
+    pub pair: String,
+    #[serde(rename = "type")]
+    pub type_field: Side,
+    pub ordertype: OrderType,
+    pub price: Decimal,
+    pub price2: Decimal,
+    pub leverage: String,
+    pub order: String,
+    pub close: String,
+}
+
+// entry of OpenOrders, ClosedOrders and QueryOrders, `id` is the txid kraken keys it by
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    pub id: String,
+    #[serde(default)]
+    pub refid: Option<String>,
+    #[serde(default)]
+    pub userref: Option<i64>,
+    #[serde(default)]
+    pub cl_ord_id: Option<String>,
+    pub status: String,
+    #[serde(rename = "opentm")]
+    pub open_time: f64,
+    #[serde(rename = "starttm")]
+    pub start_time: f64,
+    #[serde(rename = "expiretm")]
+    pub expire_time: f64,
+    #[serde(default, rename = "closetm")]
+    pub close_time: Option<f64>,
+    pub descr: OrderDetails,
+    pub vol: Decimal,
+    pub vol_exec: Decimal,
+    pub cost: Decimal,
+    pub fee: Decimal,
+    pub price: Decimal,
+    #[serde(rename = "stopprice")]
+    pub stop_price: Decimal,
+    #[serde(rename = "limitprice")]
+    pub limit_price: Decimal,
+    pub misc: String,
+    pub oflags: String,
+    #[serde(default)]
+    pub trades: Vec<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
 // kraken response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KraRre<T> {
     pub error: Vec<String>,
+    // missing when the call failed
+    #[serde(default)]
     pub result: T,
 }
 
@@ -60,6 +327,20 @@ pub struct AssetPairs {
     pub short_position_limit: i64,
 }
 
+impl AssetPairs {
+    pub fn format_price(&self, price: Decimal) -> String {
+        price.format(self.pair_decimals)
+    }
+
+    pub fn format_qty(&self, qty: Decimal) -> String {
+        qty.format(self.lot_decimals)
+    }
+
+    pub fn format_cost(&self, cost: Decimal) -> String {
+        cost.format(self.cost_decimals)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerTime {
@@ -70,46 +351,39 @@ pub struct ServerTime {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Balance {
-    #[serde(deserialize_with = "str_to_f64")]
-    pub balance: f64,
+    pub balance: Decimal,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
 pub struct BalanceEx {
-    #[serde(deserialize_with = "str_to_f64")]
-    pub balance: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub credit: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub credit_used: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub hold_trade: f64,
+    pub balance: Decimal,
+    #[serde(default)]
+    pub credit: Decimal,
+    #[serde(default)]
+    pub credit_used: Decimal,
+    pub hold_trade: Decimal,
+}
+
+impl BalanceEx {
+    // what can still be spent, kraken's own formula
+    pub fn available(&self) -> Decimal {
+        self.balance + self.credit - self.credit_used - self.hold_trade
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BalanceTrade {
-    #[serde(deserialize_with = "str_to_f64")]
-    pub eb: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub tb: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub m: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub uv: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub n: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub c: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub v: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub e: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub mf: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub mfo: f64,
+    pub eb: Decimal,
+    pub tb: Decimal,
+    pub m: Decimal,
+    pub uv: Decimal,
+    pub n: Decimal,
+    pub c: Decimal,
+    pub v: Decimal,
+    pub e: Decimal,
+    pub mf: Decimal,
+    pub mfo: Decimal,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -128,20 +402,42 @@ pub struct Trade {
     pub ordertype: String,
     #[serde(rename = "tradeordertype")]
     pub trade_ordertype: String,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub price: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub cost: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub fee: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub vol: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub margin: f64,
-    #[serde(deserialize_with = "str_to_f64")]
-    pub leverage: f64,
+    pub price: Decimal,
+    pub cost: Decimal,
+    pub fee: Decimal,
+    pub vol: Decimal,
+    pub margin: Decimal,
+    pub leverage: Decimal,
     pub misc: String,
     #[serde(rename = "trade_id")]
     pub trade_id: i64,
     pub maker: bool,
-}
\ No newline at end of file
+    // ids of the ledger entries, only sent when asked for with `ledgers`
+    #[serde(default)]
+    pub ledgers: Vec<String>,
+}
+
+// one page of a paged history call, `count` is the total over all pages
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct HistoryPage<T> {
+    pub items: Vec<T>,
+    // missing when the call was made with `without_count`
+    pub count: Option<i64>,
+}
+
+// entry of Ledgers and QueryLedgers, `id` is the ledger id kraken keys it by
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub id: String,
+    pub refid: String,
+    pub time: f64,
+    #[serde(rename = "type")]
+    pub ledger_type: LedgerType,
+    #[serde(default)]
+    pub subtype: String,
+    pub aclass: String,
+    pub asset: String,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub balance: Decimal,
+}
@@ -1,12 +1,104 @@
+use std::env;
+
 pub const BASE_URL: &str = "https://api.kraken.com";
+pub const WEBSOCKET_URL: &str = "wss://ws.kraken.com/v2";
+pub const WEBSOCKET_AUTH_URL: &str = "wss://ws-auth.kraken.com/v2";
+pub const WEBSOCKET_L3_URL: &str = "wss://ws-l3.kraken.com/v2";
 
 pub const OHLC_URL: &str = "/0/public/OHLC";
 pub const SERVER_TIME_URL: &str = "/0/public/Time";
 pub const ASSET_PAIRS_URL: &str = "/0/public/AssetPairs";
+pub const TRADES_URL: &str = "/0/public/Trades";
+pub const TICKER_URL: &str = "/0/public/Ticker";
+pub const DEPTH_URL: &str = "/0/public/Depth";
+pub const SPREAD_URL: &str = "/0/public/Spread";
+pub const ASSETS_URL: &str = "/0/public/Assets";
+pub const SYSTEM_STATUS_URL: &str = "/0/public/SystemStatus";
 
 // auth
 pub const BALANCE_URL: &str = "/0/private/Balance";
 pub const BALANCE_EX_URL: &str = "/0/private/BalanceEx";
 pub const BALANCE_TRADE_URL: &str = "/0/private/TradeBalance";
 pub const TRADES_HISTORY_URL: &str = "/0/private/TradesHistory";
+pub const LEDGERS_URL: &str = "/0/private/Ledgers";
+pub const QUERY_LEDGERS_URL: &str = "/0/private/QueryLedgers";
 pub const ADD_ORDER_URL: &str = "/0/private/AddOrder";
+pub const ADD_ORDER_BATCH_URL: &str = "/0/private/AddOrderBatch";
+pub const OPEN_ORDERS_URL: &str = "/0/private/OpenOrders";
+pub const CLOSED_ORDERS_URL: &str = "/0/private/ClosedOrders";
+pub const QUERY_ORDERS_URL: &str = "/0/private/QueryOrders";
+pub const EDIT_ORDER_URL: &str = "/0/private/EditOrder";
+pub const AMEND_ORDER_URL: &str = "/0/private/AmendOrder";
+pub const CANCEL_ORDER_URL: &str = "/0/private/CancelOrder";
+pub const CANCEL_ALL_URL: &str = "/0/private/CancelAll";
+pub const CANCEL_ALL_AFTER_URL: &str = "/0/private/CancelAllOrdersAfter";
+pub const WEBSOCKET_TOKEN_URL: &str = "/0/private/GetWebSocketsToken";
+
+// env overrides, handy for pointing the client at a local mock exchange
+pub const REST_URL_ENV: &str = "KRAKEN_REST_URL";
+pub const WEBSOCKET_URL_ENV: &str = "KRAKEN_WS_URL";
+pub const WEBSOCKET_AUTH_URL_ENV: &str = "KRAKEN_WS_AUTH_URL";
+pub const WEBSOCKET_L3_URL_ENV: &str = "KRAKEN_WS_L3_URL";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoints {
+    pub rest: String,
+    pub ws_public: String,
+    pub ws_private: String,
+    // the level3 channel is only served here, authenticated like ws_private
+    pub ws_level3: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            rest: BASE_URL.into(),
+            ws_public: WEBSOCKET_URL.into(),
+            ws_private: WEBSOCKET_AUTH_URL.into(),
+            ws_level3: WEBSOCKET_L3_URL.into(),
+        }
+    }
+}
+
+impl Endpoints {
+    // overrides go through the builder methods, so they are normalized the same way
+    pub fn from_env() -> Self {
+        let mut endpoints = Self::default();
+
+        if let Ok(url) = env::var(REST_URL_ENV) {
+            endpoints = endpoints.rest(&url);
+        }
+        if let Ok(url) = env::var(WEBSOCKET_URL_ENV) {
+            endpoints = endpoints.ws_public(&url);
+        }
+        if let Ok(url) = env::var(WEBSOCKET_AUTH_URL_ENV) {
+            endpoints = endpoints.ws_private(&url);
+        }
+        if let Ok(url) = env::var(WEBSOCKET_L3_URL_ENV) {
+            endpoints = endpoints.ws_level3(&url);
+        }
+
+        endpoints
+    }
+
+    // paths are appended to the base, a trailing '/' would make them start with "//"
+    pub fn rest(mut self, url: &str) -> Self {
+        self.rest = url.trim_end_matches('/').into();
+        self
+    }
+
+    pub fn ws_public(mut self, url: &str) -> Self {
+        self.ws_public = url.into();
+        self
+    }
+
+    pub fn ws_private(mut self, url: &str) -> Self {
+        self.ws_private = url.into();
+        self
+    }
+
+    pub fn ws_level3(mut self, url: &str) -> Self {
+        self.ws_level3 = url.into();
+        self
+    }
+}
@@ -1,16 +1,31 @@
-use std::{num::ParseFloatError, sync::Arc};
+use std::sync::Arc;
 
 use futures_util::future::ok;
 use serde_json::Value;
 
 use crate::{
     KraSoc, Kraken,
-    fetch::{error::FetchError, types::AssetPairs},
+    fetch::{
+        error::FetchError,
+        types::{AssetPairs, PublicTrade},
+    },
     pp_json, rfc3339_to_epoch,
-    types::types::CandleStick,
+    types::{
+        decimal::{Decimal, DecimalError},
+        types::CandleStick,
+    },
     utils::{NestedParseError, epoch_to_rfc3339, nested_object},
 };
 
+pub const DEFAULT_CAPACITY: usize = 700;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CandleType {
+    #[default]
+    CandleStick,
+    HeikinAshi,
+}
+
 #[derive(Debug, Clone)]
 pub struct Candle {
     pub pair: String,
@@ -18,6 +33,13 @@ pub struct Candle {
     kraken: Arc<Kraken>,
     asset_pair: AssetPairs,
     pub candles: Vec<CandleStick>,
+    // derived from `candles`, newest first like the raw series
+    pub heikin_ashi: Vec<CandleStick>,
+    pub candle_type: CandleType,
+    // most candles kept in memory, older ones are dropped as new ones stream in
+    capacity: usize,
+    // candles rebuilt from trades on top of the OHLC ones, reloaded whenever the series is refetched
+    history: usize,
 }
 
 type RawCandleStick = (u64, String, String, String, String, String, String, i64);
@@ -28,7 +50,7 @@ pub enum InitCandleError {
     FetchCandle(#[from] FetchError),
 
     #[error(transparent)]
-    ParseFloat(#[from] ParseFloatError),
+    ParseDecimal(#[from] DecimalError),
 }
 
 impl Candle {
@@ -37,20 +59,161 @@ impl Candle {
         let raw_sticks = kraken.get_ohlc(pair, &interval.to_string(), "0").await?;
         let candles = Self::build_candle_sticks(raw_sticks, pair, interval)?;
 
-        Ok(Self {
+        let mut candle = Self {
             pair: pair.into(),
             interval,
             kraken,
             asset_pair,
             candles,
-        })
+            heikin_ashi: Vec::new(),
+            candle_type: CandleType::default(),
+            capacity: DEFAULT_CAPACITY,
+            history: 0,
+        };
+
+        candle.build_heikin_ashi();
+
+        Ok(candle)
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    // kraken's OHLC endpoint stops at 720 candles, older ones are rebuilt from public trades.
+    // loads `count` candles before the oldest one, the capacity grows to keep them
+    pub async fn load_history(&mut self, count: usize) -> Result<(), InitCandleError> {
+        self.fetch_history(count).await?;
+        self.history += count;
+
+        Ok(())
+    }
+
+    // trades can only be paged forward, so paging starts where the history should begin
+    async fn fetch_history(&mut self, count: usize) -> Result<(), InitCandleError> {
+        let Some(oldest) = self.candles.last().map(|c| c.epoch) else {
+            return Ok(());
+        };
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        self.capacity = self.capacity.max(self.candles.len() + count);
+
+        let start = oldest.saturating_sub(count as u64 * self.step());
+        let mut since = (start as u128 * 1_000_000_000).to_string();
+        let mut trades: Vec<PublicTrade> = Vec::new();
+
+        loop {
+            let page = self.kraken.get_trades(&self.pair, &since).await?;
+            let done = page.last == since || page.trades.last().is_none_or(|t| t.time >= oldest as f64);
+
+            trades.extend(page.trades.into_iter().filter(|t| t.time < oldest as f64));
+
+            if done {
+                break;
+            }
+            since = page.last;
+        }
+
+        let mut older = self.resample(&trades, start, oldest);
+        self.candles.append(&mut older);
+        self.build_heikin_ashi();
+
+        Ok(())
+    }
+
+    fn step(&self) -> u64 {
+        self.interval as u64 * 60
+    }
+
+    // buckets trades into candles between `from` and `to`, newest first.
+    // a bucket without trades repeats the previous close, so the chart has no holes
+    fn resample(&self, trades: &[PublicTrade], from: u64, to: u64) -> Vec<CandleStick> {
+        let step = self.step();
+        let scale = self.asset_pair.pair_decimals.max(0) as u32;
+
+        let mut candles: Vec<CandleStick> = Vec::new();
+        let mut trades = trades.iter().peekable();
+        let mut epoch = from - from % step;
+
+        while epoch < to {
+            let end = epoch + step;
+            let mut bucket: Vec<&PublicTrade> = Vec::new();
+
+            while let Some(trade) = trades.next_if(|t| (t.time as u64) < end) {
+                if trade.time as u64 >= epoch {
+                    bucket.push(trade);
+                }
+            }
+
+            let prev_close = candles.last().map(|c| c.close);
+            let candle = match (bucket.first(), bucket.last(), prev_close) {
+                (Some(first), Some(last), _) => {
+                    let volume: Decimal = bucket.iter().map(|t| t.volume).sum();
+                    let notional: Decimal = bucket.iter().map(|t| t.price * t.volume).sum();
+
+                    CandleStick {
+                        open: first.price,
+                        high: bucket.iter().map(|t| t.price).max().unwrap_or(first.price),
+                        low: bucket.iter().map(|t| t.price).min().unwrap_or(first.price),
+                        close: last.price,
+                        trades: bucket.len() as i64,
+                        volume,
+                        vwap: notional.checked_div(volume, scale).unwrap_or(last.price),
+                        ..self.empty_candle(epoch)
+                    }
+                }
+                (_, _, Some(close)) => CandleStick {
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    vwap: close,
+                    ..self.empty_candle(epoch)
+                },
+                // nothing traded yet, history starts at the first trade
+                _ => {
+                    epoch = end;
+                    continue;
+                }
+            };
+
+            candles.push(candle);
+            epoch = end;
+        }
+
+        candles.reverse();
+        candles
+    }
+
+    fn empty_candle(&self, epoch: u64) -> CandleStick {
+        CandleStick {
+            symbol: self.pair.clone(),
+            open: Decimal::ZERO,
+            high: Decimal::ZERO,
+            low: Decimal::ZERO,
+            close: Decimal::ZERO,
+            trades: 0,
+            volume: Decimal::ZERO,
+            vwap: Decimal::ZERO,
+            interval_begin: epoch_to_rfc3339(epoch),
+            epoch,
+            interval: self.interval,
+            timestamp: String::new(),
+        }
     }
 
     pub async fn update_interval(&mut self, interval: i64) -> Result<(), InitCandleError> {
         let raw_sticks = self.kraken.get_ohlc(&self.pair, &interval.to_string(), "0").await?;
         self.candles = Self::build_candle_sticks(raw_sticks, &self.pair, interval)?;
-
         self.interval = interval;
+        self.build_heikin_ashi();
+
+        // the refetch only covers what OHLC returns, rebuild the history on top of it
+        self.fetch_history(self.history).await?;
 
         Ok(())
     }
@@ -64,7 +227,12 @@ impl Candle {
         Ok(())
     }
 
-    fn build_candle_sticks(raw_candles: Vec<RawCandleStick>, pair: &str, interval: i64) -> Result<Vec<CandleStick>, ParseFloatError> {
+    // refetch the candles over REST, used after the socket reconnected and missed updates
+    pub async fn resync(&mut self) -> Result<(), InitCandleError> {
+        self.update_interval(self.interval).await
+    }
+
+    fn build_candle_sticks(raw_candles: Vec<RawCandleStick>, pair: &str, interval: i64) -> Result<Vec<CandleStick>, DecimalError> {
         let mut candles: Vec<CandleStick> = Vec::new();
 
         for raw_candle in raw_candles {
@@ -79,7 +247,7 @@ impl Candle {
                 vwap: raw_candle.5.parse()?,
                 interval_begin: epoch_to_rfc3339(raw_candle.0),
                 epoch: raw_candle.0,
-                interval: interval,
+                interval,
                 timestamp: String::new(),
             });
         }
@@ -89,7 +257,53 @@ impl Candle {
         Ok(candles)
     }
 
-    pub fn ohlc(&self, index: usize) -> [f64; 4] {
+    pub fn update_candle_type(&mut self, candle_type: CandleType) {
+        self.candle_type = candle_type;
+    }
+
+    // the series the chart draws, depends on `candle_type`
+    pub fn series(&self) -> &[CandleStick] {
+        match self.candle_type {
+            CandleType::CandleStick => &self.candles,
+            CandleType::HeikinAshi => &self.heikin_ashi,
+        }
+    }
+
+    fn build_heikin_ashi(&mut self) {
+        let mut heikin_ashi: Vec<CandleStick> = Vec::with_capacity(self.candles.len());
+
+        for candle in self.candles.iter().rev() {
+            let ha = self.heikin_ashi_candle(candle, heikin_ashi.last());
+            heikin_ashi.push(ha);
+        }
+
+        heikin_ashi.reverse();
+        self.heikin_ashi = heikin_ashi;
+    }
+
+    // open is the midpoint of the previous heikin ashi body, the oldest candle uses its own body
+    fn heikin_ashi_candle(&self, candle: &CandleStick, prev: Option<&CandleStick>) -> CandleStick {
+        let scale = self.asset_pair.pair_decimals.max(0) as u32;
+        let half = Decimal::new(5, 1);
+        let quarter = Decimal::new(25, 2);
+
+        let close = ((candle.open + candle.high + candle.low + candle.close) * quarter).rescale(scale);
+        let open = match prev {
+            Some(prev) => (prev.open + prev.close) * half,
+            None => (candle.open + candle.close) * half,
+        }
+        .rescale(scale);
+
+        CandleStick {
+            open,
+            close,
+            high: candle.high.max(open).max(close),
+            low: candle.low.min(open).min(close),
+            ..candle.clone()
+        }
+    }
+
+    pub fn ohlc(&self, index: usize) -> [Decimal; 4] {
         [
             self.candles[index].open,
             self.candles[index].high,
@@ -138,13 +352,19 @@ impl Candle {
             self.candles[0].vwap = data.vwap;
             self.candles[0].volume = data.volume;
             self.candles[0].trades = data.trades;
+
+            self.heikin_ashi[0] = self.heikin_ashi_candle(&self.candles[0], self.heikin_ashi.get(1));
         } else {
             let mut new_candle = data.clone();
             new_candle.epoch = epoch_socket;
+
+            let ha = self.heikin_ashi_candle(&new_candle, self.heikin_ashi.first());
+            self.heikin_ashi.insert(0, ha);
             self.candles.insert(0, new_candle);
 
-            if self.candles.len() > 700 {
-                self.candles.split_off(700);
+            if self.candles.len() > self.capacity {
+                self.candles.truncate(self.capacity);
+                self.heikin_ashi.truncate(self.capacity);
             }
         }
     }
@@ -156,9 +376,9 @@ impl Candle {
         if depth == 0 {
             depth = self.candles.len();
         }
-        for c in self.candles.iter().take(depth) {
-            min = min.min(c.open).min(c.high).min(c.low).min(c.close);
-            max = max.max(c.open).max(c.high).max(c.low).max(c.close);
+        for c in self.series().iter().take(depth) {
+            min = min.min(c.low.to_f64());
+            max = max.max(c.high.to_f64());
         }
 
         (min, max)
@@ -184,3 +404,92 @@ impl Candle {
         }
     }
 }
+
+#[cfg(all(test, feature = "testkit"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testkit::{
+        MockKraken,
+        fixtures::{MOCK_CANDLES, MOCK_EPOCH},
+    };
+
+    #[tokio::test]
+    async fn load_history_prepends_older_candles() {
+        let server = MockKraken::new().start().await.unwrap();
+        let mut candle = Candle::new(Arc::new(server.kraken()), "BTC/EUR", 1).await.unwrap().capacity(MOCK_CANDLES);
+
+        let oldest = candle.candles.last().unwrap().epoch;
+        candle.load_history(30).await.unwrap();
+
+        assert_eq!(candle.candles.len(), MOCK_CANDLES + 30);
+        assert_eq!(candle.heikin_ashi.len(), candle.candles.len());
+        assert_eq!(candle.candles.last().unwrap().epoch, oldest - 30 * 60);
+        assert!(candle.candles.windows(2).all(|w| w[0].epoch == w[1].epoch + 60));
+        assert!(candle.candles[MOCK_CANDLES..].iter().all(|c| c.trades > 0 && c.epoch < oldest));
+
+        let paged = server.requests().iter().filter(|r| r.path == "/0/public/Trades").count();
+        assert!(paged > 0);
+    }
+
+    #[tokio::test]
+    async fn history_survives_a_refetch() {
+        let server = MockKraken::new().start().await.unwrap();
+        let mut candle = Candle::new(Arc::new(server.kraken()), "BTC/EUR", 1).await.unwrap();
+
+        candle.load_history(20).await.unwrap();
+        candle.resync().await.unwrap();
+        assert_eq!(candle.candles.len(), MOCK_CANDLES + 20);
+
+        candle.update_interval(5).await.unwrap();
+        assert_eq!(candle.candles.len(), MOCK_CANDLES + 20);
+        assert!(candle.candles.windows(2).all(|w| w[0].epoch == w[1].epoch + 300));
+        assert!(candle.candles.last().unwrap().epoch < MOCK_EPOCH);
+    }
+
+    fn stick(epoch: u64, [open, high, low, close]: [&str; 4]) -> CandleStick {
+        CandleStick {
+            open: open.parse().unwrap(),
+            high: high.parse().unwrap(),
+            low: low.parse().unwrap(),
+            close: close.parse().unwrap(),
+            epoch,
+            interval_begin: epoch_to_rfc3339(epoch),
+            ..Default::default()
+        }
+    }
+
+    fn ohlc(candle: &CandleStick) -> [String; 4] {
+        [&candle.open, &candle.high, &candle.low, &candle.close].map(|price| price.to_string())
+    }
+
+    #[tokio::test]
+    async fn heikin_ashi_matches_a_hand_computed_series() {
+        let server = MockKraken::new().start().await.unwrap();
+        let mut candle = Candle::new(Arc::new(server.kraken()), "BTC/EUR", 1).await.unwrap();
+
+        // newest first, prices are rounded to the pair's one decimal
+        let start = MOCK_EPOCH - MOCK_EPOCH % 60;
+        candle.candles = vec![
+            stick(start + 120, ["13", "15", "12", "14"]),
+            stick(start + 60, ["11", "14", "10", "13"]),
+            stick(start, ["10", "12", "9", "11"]),
+        ];
+        candle.build_heikin_ashi();
+
+        // open is the previous body's midpoint, 11.25 rounds to 11.3 and becomes the low of the last candle
+        let expected = [["11.3", "15", "11.3", "13.5"], ["10.5", "14", "10", "12.0"], ["10.5", "12", "9", "10.5"]];
+        assert_eq!(candle.heikin_ashi.iter().map(ohlc).collect::<Vec<_>>(), expected.map(|c| c.map(String::from)));
+
+        // an update of the running candle keeps its open and recomputes the rest
+        candle.append_streaming_data(&stick(start + 120, ["13", "16", "12", "15"]));
+        assert_eq!(ohlc(&candle.heikin_ashi[0]), ["11.3", "16", "11.3", "14.0"]);
+        assert_eq!(ohlc(&candle.heikin_ashi[1]), expected[1]);
+
+        // a new candle opens at the midpoint of the updated one, (11.3 + 14) / 2 = 12.65
+        candle.append_streaming_data(&stick(start + 180, ["15", "15", "13", "14"]));
+        assert_eq!(candle.heikin_ashi.len(), 4);
+        assert_eq!(ohlc(&candle.heikin_ashi[0]), ["12.7", "15", "12.7", "14.3"]);
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use serde_json::Value;
+
+use crate::{
+    Ch, Channel, KraSoc, Kraken,
+    fetch::{body::Side, error::FetchError, types::AssetPairs},
+    handler::orderbook::{OrderBook, OrderBookError, SyncedBook},
+    socket::channels::BookDepth,
+    types::{
+        decimal::Decimal,
+        types::{L3BookData, L3Event, L3Order},
+    },
+};
+
+// every resting order by price level, each level is a queue in time priority
+#[derive(Debug, Clone)]
+pub struct L3OrderBook {
+    kraken: Arc<Kraken>,
+    pub pair: String,
+    pub depth: BookDepth,
+    pub asset_pair: AssetPairs,
+    pub asks: BTreeMap<i64, Vec<L3Order>>,
+    pub bids: BTreeMap<Reverse<i64>, Vec<L3Order>>,
+    // side and price level of every order in the book, so deletes and modifies don't scan
+    index: HashMap<String, (Side, i64)>,
+    // set after a checksum mismatch, updates are ignored until the next snapshot
+    pub stale: bool,
+    pub checksum_mismatches: u64,
+}
+
+impl L3OrderBook {
+    // level3 only takes depths 10, 100 and 1000, the others are raised to the next one kraken accepts
+    pub async fn new(kraken: Arc<Kraken>, pair: &str, depth: BookDepth) -> Result<Self, FetchError> {
+        let asset_pair = kraken.get_asset_pair(pair).await?;
+
+        Ok(Self {
+            kraken,
+            pair: pair.into(),
+            depth: Self::supported_depth(depth),
+            asset_pair,
+            asks: BTreeMap::new(),
+            bids: BTreeMap::new(),
+            index: HashMap::new(),
+            stale: false,
+            checksum_mismatches: 0,
+        })
+    }
+
+    fn supported_depth(depth: BookDepth) -> BookDepth {
+        match depth {
+            BookDepth::D25 => BookDepth::D100,
+            BookDepth::D500 => BookDepth::D1000,
+            depth => depth,
+        }
+    }
+
+    pub async fn update_pair(&mut self, pair: &str) -> Result<(), FetchError> {
+        self.asset_pair = self.kraken.get_asset_pair(pair).await?;
+        self.pair = pair.into();
+        self.clear();
+
+        Ok(())
+    }
+
+    // private channel, subscribe on Socket::level3
+    pub fn channel(&self) -> Channel {
+        let depth = ("depth".to_string(), Value::from(Self::supported_depth(self.depth).levels()));
+        Channel::new(Ch::LEVEL3, vec![&self.pair], Some(HashMap::from([depth])))
+    }
+
+    // on a mismatch the book is cleared and marked stale, resubscribe to `channel` to get a fresh snapshot
+    pub fn stream(&mut self, data: KraSoc<L3BookData>) -> Result<(), OrderBookError> {
+        let snapshot = data.type_field == "snapshot";
+
+        match data.data.into_iter().next() {
+            Some(frame) => self.sync(frame, snapshot),
+            None => Ok(()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.asks.clear();
+        self.bids.clear();
+        self.index.clear();
+    }
+
+    // a modify keeps the order's place in the queue unless it moved to another price
+    fn apply(&mut self, side: Side, order: L3Order) {
+        let key = order.limit_price.to_fixed(self.asset_pair.pair_decimals);
+
+        match order.event.unwrap_or_default() {
+            L3Event::Delete => self.remove(&order.order_id),
+            L3Event::Modify if self.index.get(&order.order_id) == Some(&(side, key)) => {
+                if let Some(resting) = self.level_mut(side, key).iter_mut().find(|o| o.order_id == order.order_id) {
+                    resting.order_qty = order.order_qty;
+                    resting.timestamp = order.timestamp;
+                }
+            }
+            L3Event::Add | L3Event::Modify => {
+                self.remove(&order.order_id);
+                self.index.insert(order.order_id.clone(), (side, key));
+                self.level_mut(side, key).push(L3Order {
+                    event: None,
+                    ..order
+                });
+            }
+        }
+    }
+
+    fn level_mut(&mut self, side: Side, key: i64) -> &mut Vec<L3Order> {
+        match side {
+            Side::BUY => self.bids.entry(Reverse(key)).or_default(),
+            Side::SELL => self.asks.entry(key).or_default(),
+        }
+    }
+
+    fn remove(&mut self, order_id: &str) {
+        let Some((side, key)) = self.index.remove(order_id) else {
+            return;
+        };
+
+        let empty = {
+            let level = self.level_mut(side, key);
+            level.retain(|o| o.order_id != order_id);
+            level.is_empty()
+        };
+
+        if empty {
+            match side {
+                Side::BUY => self.bids.remove(&Reverse(key)),
+                Side::SELL => self.asks.remove(&key),
+            };
+        }
+    }
+
+    // levels past the subscribed depth are dropped together with their orders
+    fn truncate(&mut self) {
+        if let Some((&key, _)) = self.asks.iter().nth(self.depth.levels()) {
+            for order in self.asks.split_off(&key).into_values().flatten() {
+                self.index.remove(&order.order_id);
+            }
+        }
+
+        if let Some((&key, _)) = self.bids.iter().nth(self.depth.levels()) {
+            for order in self.bids.split_off(&key).into_values().flatten() {
+                self.index.remove(&order.order_id);
+            }
+        }
+    }
+
+    // like the L2 checksum, but over every order of the top 10 levels in queue order
+    pub fn checksum(&self) -> u32 {
+        let price_precision = self.asset_pair.pair_decimals;
+        let qty_precision = self.asset_pair.lot_decimals;
+
+        let mut checksum = String::new();
+        let orders = self.asks.values().take(10).chain(self.bids.values().take(10)).flatten();
+
+        for order in orders {
+            let price = order.limit_price.to_fixed(price_precision);
+            let qty = order.order_qty.to_fixed(qty_precision);
+            checksum.push_str(&format!("{}{}", price, qty));
+        }
+
+        crc32fast::hash(checksum.as_bytes())
+    }
+
+    pub fn order(&self, order_id: &str) -> Option<&L3Order> {
+        let (side, key) = self.index.get(order_id)?;
+        let level = match side {
+            Side::BUY => self.bids.get(&Reverse(*key)),
+            Side::SELL => self.asks.get(key),
+        }?;
+
+        level.iter().find(|o| o.order_id == order_id)
+    }
+
+    // orders and quantity ahead of `order_id` at its price level
+    pub fn queue_position(&self, order_id: &str) -> Option<(usize, Decimal)> {
+        let (side, key) = self.index.get(order_id)?;
+        let level = match side {
+            Side::BUY => self.bids.get(&Reverse(*key)),
+            Side::SELL => self.asks.get(key),
+        }?;
+
+        let ahead = level.iter().position(|o| o.order_id == order_id)?;
+        let qty = level.iter().take(ahead).map(|o| o.order_qty).sum();
+
+        Some((ahead, qty))
+    }
+
+    // the same book summed per price, its checksum matches the book channel
+    pub fn aggregate(&self) -> OrderBook {
+        let qty_precision = self.asset_pair.lot_decimals;
+        let total = |level: &Vec<L3Order>| level.iter().map(|o| o.order_qty.to_fixed(qty_precision)).sum();
+
+        let asks = self.asks.iter().map(|(price, level)| (*price, total(level))).collect();
+        let bids = self.bids.iter().map(|(price, level)| (*price, total(level))).collect();
+
+        OrderBook::from_levels(self.kraken.clone(), &self.pair, self.depth, self.asset_pair.clone(), asks, bids)
+    }
+}
+
+impl SyncedBook for L3OrderBook {
+    type Frame = L3BookData;
+
+    fn header(frame: &L3BookData) -> (&str, u32) {
+        (&frame.symbol, frame.checksum)
+    }
+
+    fn pair(&self) -> &str {
+        &self.pair
+    }
+
+    fn sync_state(&mut self) -> (&mut bool, &mut u64) {
+        (&mut self.stale, &mut self.checksum_mismatches)
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    fn apply_frame(&mut self, frame: L3BookData) {
+        for order in frame.bids {
+            self.apply(Side::BUY, order);
+        }
+
+        for order in frame.asks {
+            self.apply(Side::SELL, order);
+        }
+
+        self.truncate();
+    }
+
+    fn calculate_checksum(&self) -> u32 {
+        self.checksum()
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use crate::{
+    Ch, Channel, KraSoc,
+    fetch::body::Side,
+    types::{decimal::Decimal, types::TradeData},
+};
+
+pub const DEFAULT_CAPACITY: usize = 500;
+
+// public prints from the `trade` channel, the time and sales of one pair
+#[derive(Debug, Clone)]
+pub struct MarketTrades {
+    pub pair: String,
+    // newest first, older prints are dropped past `capacity`
+    pub prints: VecDeque<TradeData>,
+    capacity: usize,
+}
+
+impl MarketTrades {
+    pub fn new(pair: &str) -> Self {
+        Self {
+            pair: pair.into(),
+            prints: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn channel(&self) -> Channel {
+        Channel::new(Ch::TRADE, vec![&self.pair], None)
+    }
+
+    pub fn update_pair(&mut self, pair: &str) {
+        self.pair = pair.into();
+        self.clear();
+    }
+
+    // a snapshot replaces the buffer, kraken sends prints oldest first in both snapshots and updates
+    pub fn stream(&mut self, data: KraSoc<TradeData>) {
+        if data.type_field == "snapshot" {
+            self.clear();
+        }
+
+        // prints of the pair shown before `update_pair` are dropped
+        for print in data.data.into_iter().filter(|t| t.symbol == self.pair) {
+            self.prints.push_front(print);
+        }
+
+        self.prints.truncate(self.capacity);
+    }
+
+    pub fn clear(&mut self) {
+        self.prints.clear();
+    }
+
+    pub fn last(&self) -> Option<&TradeData> {
+        self.prints.front()
+    }
+
+    // traded quantity of one aggressor side over the prints held
+    pub fn volume(&self, side: Side) -> Decimal {
+        self.prints.iter().filter(|t| t.side == side).map(|t| t.qty).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(type_field: &str, prints: &[(&str, i64, Side)]) -> KraSoc<TradeData> {
+        let data = prints
+            .iter()
+            .map(|&(symbol, trade_id, side)| TradeData {
+                symbol: symbol.into(),
+                side,
+                qty: Decimal::from(trade_id),
+                trade_id,
+                ..Default::default()
+            })
+            .collect();
+
+        KraSoc {
+            type_field: type_field.into(),
+            data,
+            ..Default::default()
+        }
+    }
+
+    fn ids(trades: &MarketTrades) -> Vec<i64> {
+        trades.prints.iter().map(|t| t.trade_id).collect()
+    }
+
+    #[test]
+    fn buffer_is_trimmed_to_capacity() {
+        let mut trades = MarketTrades::new("BTC/EUR").capacity(3);
+
+        trades.stream(frame("snapshot", &[("BTC/EUR", 1, Side::BUY), ("BTC/EUR", 2, Side::BUY)]));
+        trades.stream(frame(
+            "update",
+            &[("BTC/EUR", 3, Side::SELL), ("BTC/EUR", 4, Side::BUY), ("BTC/EUR", 5, Side::SELL)],
+        ));
+
+        assert_eq!(ids(&trades), [5, 4, 3]);
+        assert_eq!(trades.last().map(|t| t.trade_id), Some(5));
+        assert_eq!(trades.volume(Side::SELL), Decimal::from(8));
+    }
+
+    #[test]
+    fn snapshot_replaces_the_buffer() {
+        let mut trades = MarketTrades::new("BTC/EUR");
+
+        trades.stream(frame("update", &[("BTC/EUR", 1, Side::BUY)]));
+        trades.stream(frame("snapshot", &[("BTC/EUR", 7, Side::BUY), ("BTC/EUR", 8, Side::SELL)]));
+
+        assert_eq!(ids(&trades), [8, 7]);
+    }
+
+    #[test]
+    fn prints_of_the_previous_pair_are_ignored() {
+        let mut trades = MarketTrades::new("BTC/EUR");
+        trades.stream(frame("update", &[("BTC/EUR", 1, Side::BUY)]));
+
+        trades.update_pair("ETH/EUR");
+        assert!(trades.prints.is_empty());
+
+        trades.stream(frame("update", &[("BTC/EUR", 2, Side::BUY), ("ETH/EUR", 3, Side::SELL)]));
+        assert_eq!(ids(&trades), [3]);
+    }
+}
@@ -1,4 +1,7 @@
 pub mod candle;
+pub mod l3_orderbook;
+pub mod market_trades;
 pub mod orderbook;
+pub mod portfolio;
 pub mod ticker;
 pub mod trades;
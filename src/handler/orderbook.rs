@@ -1,57 +1,182 @@
-use std::{cmp::Reverse, collections::BTreeMap, fmt::format, ops::Index, process::exit, sync::Arc};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    fmt::format,
+    ops::Index,
+    process::exit,
+    sync::Arc,
+};
 
 use serde_json::Value;
 
 use crate::{
-    Kraken,
-    fetch::{error::FetchError, types::AssetPairs},
-    types::types::{OrderBookData, OrderBookType},
-    utils::{NestedParseError, decode_fixed, encode_fixed, nested_object},
+    Ch, Channel, Kraken,
+    fetch::{body::Side, error::FetchError, types::AssetPairs},
+    socket::channels::BookDepth,
+    types::{
+        decimal::Decimal,
+        types::{OrderBookData, OrderBookType},
+    },
+    utils::{NestedParseError, nested_object},
 };
 
+#[derive(Debug, thiserror::Error)]
+pub enum OrderBookError {
+    #[error("Checksum did not match, expected: {expected}, calculated: {calculated}")]
+    ChecksumMismatch { expected: u32, calculated: u32 },
+}
+
+// the frame handling OrderBook and L3OrderBook share, they only differ in how a frame's levels are applied
+pub(crate) trait SyncedBook {
+    type Frame;
+
+    // symbol and checksum kraken sent with the frame
+    fn header(frame: &Self::Frame) -> (&str, u32);
+    fn pair(&self) -> &str;
+    // the book's `stale` flag and `checksum_mismatches` count
+    fn sync_state(&mut self) -> (&mut bool, &mut u64);
+    fn reset(&mut self);
+    // applies the frame and drops the levels past the subscribed depth
+    fn apply_frame(&mut self, frame: Self::Frame);
+    fn calculate_checksum(&self) -> u32;
+
+    // frames for the previous pair can still arrive right after switching, they are skipped.
+    // on a mismatch the book is cleared and marked stale, updates are ignored until the next snapshot
+    fn sync(&mut self, frame: Self::Frame, snapshot: bool) -> Result<(), OrderBookError> {
+        let (symbol, expected) = Self::header(&frame);
+        if symbol != self.pair() {
+            return Ok(());
+        }
+
+        if snapshot {
+            self.reset();
+            *self.sync_state().0 = false;
+        }
+
+        if *self.sync_state().0 {
+            return Ok(());
+        }
+
+        self.apply_frame(frame);
+
+        let calculated = self.calculate_checksum();
+        if calculated != expected {
+            self.reset();
+
+            let (stale, mismatches) = self.sync_state();
+            *stale = true;
+            *mismatches += 1;
+
+            return Err(OrderBookError::ChecksumMismatch {
+                expected,
+                calculated,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// what a market order of some size would get from the book as it is now
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub filled: Decimal,
+    pub avg_price: Decimal,
+    // last level the order reaches
+    pub worst_price: Decimal,
+    // distance of the average price from the best price, always >= 0
+    pub slippage: Decimal,
+    pub slippage_bps: Decimal,
+    // false when the held levels hold less than the size
+    pub complete: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct OrderBook {
     kraken: Arc<Kraken>,
+    pub pair: String,
+    pub depth: BookDepth,
     pub asset_pair: AssetPairs,
     pub asks: BTreeMap<i64, i64>,
     pub bids: BTreeMap<Reverse<i64>, i64>,
+    // set after a checksum mismatch, updates are ignored until the next snapshot
+    pub stale: bool,
+    pub checksum_mismatches: u64,
 }
 
 impl OrderBook {
-    pub async fn new(kraken: Arc<Kraken>, pair: &str) -> Result<Self, FetchError> {
+    pub async fn new(kraken: Arc<Kraken>, pair: &str, depth: BookDepth) -> Result<Self, FetchError> {
         let asset_pair = kraken.get_asset_pair(pair).await?;
 
         Ok(Self {
             kraken,
+            pair: pair.into(),
+            depth,
             asset_pair,
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
+            stale: false,
+            checksum_mismatches: 0,
         })
     }
 
-    pub fn stream(&mut self, data: OrderBookType) {
-        self.update(&data.data[0]);
-
-        if let Some((&key, _)) = self.asks.iter().nth(10) {
-            self.asks.split_off(&key);
+    // a book whose levels come from somewhere else, the level3 book aggregates into this
+    pub(crate) fn from_levels(
+        kraken: Arc<Kraken>,
+        pair: &str,
+        depth: BookDepth,
+        asset_pair: AssetPairs,
+        asks: BTreeMap<i64, i64>,
+        bids: BTreeMap<Reverse<i64>, i64>,
+    ) -> Self {
+        Self {
+            kraken,
+            pair: pair.into(),
+            depth,
+            asset_pair,
+            asks,
+            bids,
+            stale: false,
+            checksum_mismatches: 0,
         }
+    }
 
-        if let Some((&key, _)) = self.bids.iter().nth(10) {
-            self.bids.split_off(&key);
-        }
+    pub async fn update_pair(&mut self, pair: &str) -> Result<(), FetchError> {
+        self.asset_pair = self.kraken.get_asset_pair(pair).await?;
+        self.pair = pair.into();
+        self.clear();
+
+        Ok(())
+    }
 
-        if (self.checksum() != data.data[0].checksum) {
-            panic!("ERROR: checksum did not match")
+    pub fn channel(&self) -> Channel {
+        let depth = ("depth".to_string(), Value::from(self.depth.levels()));
+        Channel::new(Ch::BOOK, vec![&self.pair], Some(HashMap::from([depth])))
+    }
+
+    // on a mismatch the book is cleared and marked stale, resubscribe to `channel` to get a fresh snapshot
+    pub fn stream(&mut self, data: OrderBookType) -> Result<(), OrderBookError> {
+        let snapshot = data.type_field == "snapshot";
+
+        match data.data.into_iter().next() {
+            Some(frame) => self.sync(frame, snapshot),
+            None => Ok(()),
         }
     }
 
+    // drops every level, the next snapshot rebuilds the book
+    pub fn clear(&mut self) {
+        self.asks.clear();
+        self.bids.clear();
+    }
+
     fn update(&mut self, data: &OrderBookData) {
         let price_precision = self.asset_pair.pair_decimals;
         let qty_precision = self.asset_pair.lot_decimals;
 
         for bid in data.bids.iter() {
-            let key = encode_fixed(price_precision, bid.price);
-            let qty = encode_fixed(qty_precision, bid.qty);
+            let key = bid.price.to_fixed(price_precision);
+            let qty = bid.qty.to_fixed(qty_precision);
 
             match qty {
                 0 => self.bids.remove(&Reverse(key)),
@@ -60,8 +185,8 @@ impl OrderBook {
         }
 
         for ask in data.asks.iter() {
-            let key = encode_fixed(price_precision, ask.price);
-            let qty = encode_fixed(qty_precision, ask.qty);
+            let key = ask.price.to_fixed(price_precision);
+            let qty = ask.qty.to_fixed(qty_precision);
 
             match qty {
                 0 => self.asks.remove(&key),
@@ -70,18 +195,18 @@ impl OrderBook {
         }
     }
 
-    pub fn checksum(&mut self) -> u32 {
+    pub fn checksum(&self) -> u32 {
         let price_precision = self.asset_pair.pair_decimals;
         let qty_precision = self.asset_pair.lot_decimals;
 
         let mut ask_string = String::new();
-        for entry in self.asks.iter() {
+        for entry in self.asks.iter().take(10) {
             let str = format!("{}{}", entry.0, entry.1.clone());
             ask_string.push_str(&str);
         }
 
         let mut bid_string = String::new();
-        for entry in self.bids.iter() {
+        for entry in self.bids.iter().take(10) {
             let str = format!("{}{}", entry.0.0, entry.1.clone());
             bid_string.push_str(&str);
         }
@@ -90,22 +215,127 @@ impl OrderBook {
         crc32fast::hash(final_string.as_bytes())
     }
 
-    pub fn price_decoded(&self, price: i64) -> f64 {
-        decode_fixed(self.asset_pair.pair_decimals, price)
+    pub fn price_decoded(&self, price: i64) -> Decimal {
+        Decimal::from_fixed(price, self.asset_pair.pair_decimals)
     }
 
-    pub fn qty_decoded(&self, qty: i64) -> f64 {
-        decode_fixed(self.asset_pair.lot_decimals, qty)
+    pub fn qty_decoded(&self, qty: i64) -> Decimal {
+        Decimal::from_fixed(qty, self.asset_pair.lot_decimals)
     }
 
-    pub fn ask_decode(&self, pair: (&i64, &i64)) -> (f64, f64) {
+    pub fn ask_decode(&self, pair: (&i64, &i64)) -> (Decimal, Decimal) {
         (self.price_decoded(*pair.0), self.qty_decoded(*pair.1))
     }
 
-    pub fn bid_decode(&self, pair: (&Reverse<i64>, &i64)) -> (f64, f64) {
+    pub fn bid_decode(&self, pair: (&Reverse<i64>, &i64)) -> (Decimal, Decimal) {
         (self.price_decoded(pair.0.0), self.qty_decoded(*pair.1))
     }
 
+    pub fn best_bid(&self) -> Option<(Decimal, Decimal)> {
+        self.bids.iter().next().map(|level| self.bid_decode(level))
+    }
+
+    pub fn best_ask(&self) -> Option<(Decimal, Decimal)> {
+        self.asks.iter().next().map(|level| self.ask_decode(level))
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()?.0 - self.best_bid()?.0)
+    }
+
+    pub fn spread_bps(&self) -> Option<Decimal> {
+        Self::bps(self.spread()?, self.mid()?)
+    }
+
+    pub fn mid(&self) -> Option<Decimal> {
+        let (bid, ask) = (self.best_bid()?.0, self.best_ask()?.0);
+        Some((bid + ask) * Decimal::new(5, 1))
+    }
+
+    // mid weighted by the size on the other side, leans towards the side that is about to run out
+    pub fn microprice(&self) -> Option<Decimal> {
+        let ((bid, bid_qty), (ask, ask_qty)) = (self.best_bid()?, self.best_ask()?);
+        let weighted = ask.checked_mul(bid_qty)?.checked_add(bid.checked_mul(ask_qty)?)?;
+        weighted.checked_div(bid_qty.checked_add(ask_qty)?, self.price_scale() + 2)
+    }
+
+    // quantity over the best `levels` price levels of one side, BUY are the bids
+    pub fn volume(&self, side: Side, levels: usize) -> Decimal {
+        // summed as decimals, a few levels near the i64 bounds would overflow the fixed form
+        match side {
+            Side::BUY => self.bids.values().take(levels).map(|&qty| self.qty_decoded(qty)).sum(),
+            Side::SELL => self.asks.values().take(levels).map(|&qty| self.qty_decoded(qty)).sum(),
+        }
+    }
+
+    // (price, quantity up to and including that level) from the best price outwards, BUY are the bids
+    pub fn cumulative(&self, side: Side) -> Vec<(Decimal, Decimal)> {
+        let levels: Vec<(Decimal, Decimal)> = match side {
+            Side::BUY => self.bids.iter().map(|level| self.bid_decode(level)).collect(),
+            Side::SELL => self.asks.iter().map(|level| self.ask_decode(level)).collect(),
+        };
+
+        let mut total = Decimal::ZERO;
+        levels
+            .into_iter()
+            .map(|(price, qty)| {
+                total = total + qty;
+                (price, total)
+            })
+            .collect()
+    }
+
+    // (bids - asks) / (bids + asks) over the best `levels`, from -1 all asks to 1 all bids
+    pub fn imbalance(&self, levels: usize) -> Option<Decimal> {
+        let (bids, asks) = (self.volume(Side::BUY, levels), self.volume(Side::SELL, levels));
+        (bids - asks).checked_div(bids + asks, 4)
+    }
+
+    // walks the other side of the book for a market order of `qty`, a buy takes the asks
+    pub fn fill(&self, side: Side, qty: Decimal) -> Option<Fill> {
+        let levels: Vec<(Decimal, Decimal)> = match side {
+            Side::BUY => self.asks.iter().map(|level| self.ask_decode(level)).collect(),
+            Side::SELL => self.bids.iter().map(|level| self.bid_decode(level)).collect(),
+        };
+
+        let best = levels.first()?.0;
+        let mut filled = Decimal::ZERO;
+        let mut notional = Decimal::ZERO;
+        let mut worst_price = best;
+
+        for (price, available) in levels {
+            if filled >= qty {
+                break;
+            }
+
+            // `qty` comes from the caller, so the sums are checked rather than trusted to fit
+            let take = available.min(qty.checked_sub(filled)?);
+            filled = filled.checked_add(take)?;
+            notional = notional.checked_add(price.checked_mul(take)?)?;
+            worst_price = price;
+        }
+
+        let avg_price = notional.checked_div(filled, self.price_scale() + 2)?;
+        let slippage = (avg_price - best).abs();
+
+        Some(Fill {
+            filled,
+            avg_price,
+            worst_price,
+            slippage,
+            slippage_bps: Self::bps(slippage, best)?,
+            complete: filled >= qty,
+        })
+    }
+
+    fn price_scale(&self) -> u32 {
+        self.asset_pair.pair_decimals.max(0) as u32
+    }
+
+    fn bps(value: Decimal, of: Decimal) -> Option<Decimal> {
+        (value * Decimal::from(10_000)).checked_div(of, 2)
+    }
+
     pub fn print_table(&mut self, size: u8) {
         println!("Update orderbook, bid: {}, ask: {}", self.bids.len(), self.asks.len());
 
@@ -124,3 +354,132 @@ impl OrderBook {
         println!();
     }
 }
+
+impl SyncedBook for OrderBook {
+    type Frame = OrderBookData;
+
+    fn header(frame: &OrderBookData) -> (&str, u32) {
+        (&frame.symbol, frame.checksum)
+    }
+
+    fn pair(&self) -> &str {
+        &self.pair
+    }
+
+    fn sync_state(&mut self) -> (&mut bool, &mut u64) {
+        (&mut self.stale, &mut self.checksum_mismatches)
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    fn apply_frame(&mut self, frame: OrderBookData) {
+        self.update(&frame);
+
+        if let Some((&key, _)) = self.asks.iter().nth(self.depth.levels()) {
+            self.asks.split_off(&key);
+        }
+
+        if let Some((&key, _)) = self.bids.iter().nth(self.depth.levels()) {
+            self.bids.split_off(&key);
+        }
+    }
+
+    fn calculate_checksum(&self) -> u32 {
+        self.checksum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    // prices with one decimal, quantities with two
+    fn book(asks: &[(i64, i64)], bids: &[(i64, i64)]) -> OrderBook {
+        let asset_pair = AssetPairs {
+            pair_decimals: 1,
+            lot_decimals: 2,
+            ..Default::default()
+        };
+
+        let asks = asks.iter().copied().collect();
+        let bids = bids.iter().map(|&(price, qty)| (Reverse(price), qty)).collect();
+
+        OrderBook::from_levels(Arc::new(Kraken::new("", "")), "BTC/EUR", BookDepth::D10, asset_pair, asks, bids)
+    }
+
+    fn sample() -> OrderBook {
+        book(&[(1000, 100), (1010, 200), (1030, 100)], &[(990, 150), (980, 50)])
+    }
+
+    #[test]
+    fn partial_fill_within_the_best_level() {
+        let fill = sample().fill(Side::BUY, dec("0.5")).unwrap();
+
+        assert_eq!(fill.filled, dec("0.5"));
+        assert_eq!(fill.avg_price, dec("100"));
+        assert_eq!(fill.worst_price, dec("100"));
+        assert_eq!(fill.slippage, Decimal::ZERO);
+        assert!(fill.complete);
+    }
+
+    #[test]
+    fn fill_spanning_several_levels() {
+        // 1 at 100 and 1.5 at 101
+        let fill = sample().fill(Side::BUY, dec("2.5")).unwrap();
+
+        assert_eq!(fill.filled, dec("2.5"));
+        assert_eq!(fill.avg_price, dec("100.6"));
+        assert_eq!(fill.worst_price, dec("101"));
+        assert_eq!(fill.slippage, dec("0.6"));
+        assert_eq!(fill.slippage_bps, dec("60"));
+        assert!(fill.complete);
+
+        // a sell walks the bids down, 1.5 at 99 and 0.5 at 98
+        let fill = sample().fill(Side::SELL, dec("2")).unwrap();
+        assert_eq!(fill.avg_price, dec("98.75"));
+        assert_eq!(fill.worst_price, dec("98"));
+        assert_eq!(fill.slippage, dec("0.25"));
+    }
+
+    #[test]
+    fn fill_beyond_the_book_is_incomplete() {
+        let fill = sample().fill(Side::BUY, dec("10")).unwrap();
+
+        assert_eq!(fill.filled, dec("4"));
+        assert_eq!(fill.avg_price, dec("101.25"));
+        assert_eq!(fill.worst_price, dec("103"));
+        assert!(!fill.complete);
+    }
+
+    #[test]
+    fn empty_side_has_no_answers() {
+        let bids_only = book(&[], &[(990, 150)]);
+
+        assert_eq!(bids_only.fill(Side::BUY, dec("1")), None);
+        assert_eq!(bids_only.best_ask(), None);
+        assert_eq!(bids_only.spread(), None);
+        assert_eq!(bids_only.mid(), None);
+        assert_eq!(book(&[], &[]).imbalance(10), None);
+    }
+
+    #[test]
+    fn imbalance_spans_one_side_to_the_other() {
+        assert_eq!(book(&[], &[(990, 150), (980, 50)]).imbalance(10), Some(dec("1")));
+        assert_eq!(book(&[(1000, 100)], &[]).imbalance(10), Some(dec("-1")));
+
+        // 1.5 on the best bid and 1 on the best ask
+        assert_eq!(sample().imbalance(1), Some(dec("0.2")));
+    }
+
+    #[test]
+    fn volume_does_not_overflow_the_fixed_form() {
+        let book = book(&[], &[(990, i64::MAX), (980, i64::MAX)]);
+        assert_eq!(book.volume(Side::BUY, 2), Decimal::new(2 * i64::MAX as i128, 2));
+    }
+}
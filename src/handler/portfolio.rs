@@ -0,0 +1,136 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    BalanceType, Kraken,
+    fetch::{
+        error::{AuthFetchError, ErrorCategory, FetchError},
+        types::{AssetNames, BalanceEx, TickerInfo},
+    },
+    types::decimal::Decimal,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PortfolioError {
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+
+    #[error(transparent)]
+    AuthFetch(#[from] AuthFetchError),
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct PortfolioValue {
+    pub quote: String,
+    pub total: Decimal,
+    // value of every priced asset in the quote currency
+    pub assets: HashMap<String, Decimal>,
+    // held assets without a ticker against the quote, left out of `total`
+    pub unpriced: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Portfolio {
+    // keyed by the normalized asset name
+    pub balances: HashMap<String, BalanceEx>,
+    pub names: AssetNames,
+    kraken: Arc<Kraken>,
+}
+
+impl Portfolio {
+    pub async fn new(kraken: Arc<Kraken>) -> Result<Self, PortfolioError> {
+        let names = kraken.get_asset_names().await?;
+        let balances = kraken.get_balance_ex_normalized(BalanceType::default(), &names).await?.result;
+
+        Ok(Self {
+            balances,
+            names,
+            kraken,
+        })
+    }
+
+    pub async fn update_balances(&mut self) -> Result<(), AuthFetchError> {
+        self.balances = self.kraken.get_balance_ex_normalized(BalanceType::default(), &self.names).await?.result;
+
+        Ok(())
+    }
+
+    pub fn balance(&self, asset: &str) -> Option<&BalanceEx> {
+        self.balances.get(asset)
+    }
+
+    // values every non zero balance at the last traded price against `quote`
+    pub async fn value(&self, quote: &str) -> Result<PortfolioValue, FetchError> {
+        let held: Vec<(&str, Decimal)> = self
+            .balances
+            .iter()
+            .filter(|(_, balance)| balance.balance != Decimal::ZERO)
+            .map(|(asset, balance)| (asset.as_str(), balance.balance))
+            .collect();
+
+        let mut pairs: Vec<String> = held
+            .iter()
+            .map(|(asset, _)| Self::base(asset))
+            .filter(|base| *base != quote)
+            .map(|base| format!("{}/{}", base, quote))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+
+        let tickers = self.tickers(&pairs).await?;
+        let scale = self.names.find(quote).map_or(2, |info| info.display_decimals.max(0) as u32);
+        let mut value = PortfolioValue {
+            quote: quote.to_string(),
+            ..PortfolioValue::default()
+        };
+
+        for (asset, balance) in held {
+            let base = Self::base(asset);
+            let price = match base == quote {
+                true => Some(Decimal::new(1, 0)),
+                false => tickers.get(&format!("{}/{}", base, quote)).map(|ticker| ticker.last.0),
+            };
+
+            match price {
+                Some(price) => {
+                    value.total = value.total + balance * price;
+                    value.assets.insert(asset.to_string(), (balance * price).rescale(scale));
+                }
+                None => value.unpriced.push(asset.to_string()),
+            }
+        }
+
+        value.total = value.total.rescale(scale);
+        value.unpriced.sort();
+
+        Ok(value)
+    }
+
+    // kraken rejects the whole call when one pair does not exist, then every pair is asked for on its own
+    async fn tickers(&self, pairs: &[String]) -> Result<HashMap<String, TickerInfo>, FetchError> {
+        if pairs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let all: Vec<&str> = pairs.iter().map(String::as_str).collect();
+        match self.kraken.get_ticker(&all).await {
+            Err(FetchError::Exchange(error)) if error.category == ErrorCategory::Query => {}
+            result => return result,
+        }
+
+        let mut tickers = HashMap::new();
+        for pair in all {
+            match self.kraken.get_ticker(&[pair]).await {
+                Ok(ticker) => tickers.extend(ticker),
+                Err(FetchError::Exchange(error)) if error.category == ErrorCategory::Query => continue,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(tickers)
+    }
+
+    // staked and earn balances are priced like the asset itself
+    fn base(asset: &str) -> &str {
+        asset.split_once('.').map_or(asset, |(base, _)| base)
+    }
+}
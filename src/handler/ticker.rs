@@ -1,4 +1,114 @@
+use std::collections::HashMap;
 
+use crate::{
+    Ch, Channel,
+    types::{
+        decimal::Decimal,
+        types::{TickerData, TickerType},
+    },
+};
+
+// latest `ticker` frame of every watched pair, kraken sends the full ticker on each update
+#[derive(Debug, Clone, Default)]
 pub struct Ticker {
-    
-}
\ No newline at end of file
+    // in the order they were added, the strip shows them like that
+    pub pairs: Vec<String>,
+    pub tickers: HashMap<String, TickerData>,
+}
+
+impl Ticker {
+    pub fn new(pairs: &[&str]) -> Self {
+        Self {
+            pairs: pairs.iter().map(|p| p.to_string()).collect(),
+            tickers: HashMap::new(),
+        }
+    }
+
+    pub fn channel(&self) -> Channel {
+        Channel::new(Ch::TICKER, self.pairs.iter().map(String::as_str).collect(), None)
+    }
+
+    // returns the channel to subscribe when `pair` was not watched yet
+    pub fn watch(&mut self, pair: &str) -> Option<Channel> {
+        if self.pairs.iter().any(|p| p == pair) {
+            return None;
+        }
+
+        self.pairs.push(pair.into());
+        Some(Channel::new(Ch::TICKER, vec![pair], None))
+    }
+
+    pub fn stream(&mut self, data: TickerType) {
+        for ticker in data.data.into_iter().filter(|t| self.pairs.contains(&t.symbol)) {
+            self.tickers.insert(ticker.symbol.clone(), ticker);
+        }
+    }
+
+    pub fn get(&self, pair: &str) -> Option<&TickerData> {
+        self.tickers.get(pair)
+    }
+
+    pub fn spread(&self, pair: &str) -> Option<Decimal> {
+        self.get(pair).map(|t| t.ask - t.bid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(type_field: &str, tickers: &[(&str, &str, &str)]) -> TickerType {
+        let data = tickers
+            .iter()
+            .map(|&(symbol, bid, ask)| TickerData {
+                symbol: symbol.into(),
+                bid: bid.parse().unwrap(),
+                ask: ask.parse().unwrap(),
+                ..Default::default()
+            })
+            .collect();
+
+        TickerType {
+            type_field: type_field.into(),
+            data,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn snapshot_fills_the_watched_pairs() {
+        let mut ticker = Ticker::new(&["BTC/EUR", "ETH/EUR"]);
+        ticker.stream(frame(
+            "snapshot",
+            &[("BTC/EUR", "100", "101"), ("ETH/EUR", "10", "10.5"), ("SOL/EUR", "1", "2")],
+        ));
+
+        assert_eq!(ticker.spread("BTC/EUR"), Some("1".parse().unwrap()));
+        assert_eq!(ticker.spread("ETH/EUR"), Some("0.5".parse().unwrap()));
+        assert_eq!(ticker.get("SOL/EUR"), None);
+    }
+
+    #[test]
+    fn update_replaces_only_its_pair() {
+        let mut ticker = Ticker::new(&["BTC/EUR", "ETH/EUR"]);
+        ticker.stream(frame("snapshot", &[("BTC/EUR", "100", "101"), ("ETH/EUR", "10", "10.5")]));
+        ticker.stream(frame("update", &[("BTC/EUR", "102", "102.5")]));
+
+        let btc = ticker.get("BTC/EUR").unwrap();
+        assert_eq!((btc.bid, btc.ask), ("102".parse().unwrap(), "102.5".parse().unwrap()));
+        assert_eq!(ticker.spread("ETH/EUR"), Some("0.5".parse().unwrap()));
+    }
+
+    #[test]
+    fn watch_subscribes_a_pair_once() {
+        let mut ticker = Ticker::new(&["BTC/EUR"]);
+
+        assert!(ticker.watch("BTC/EUR").is_none());
+        assert_eq!(ticker.watch("ETH/EUR").map(|c| c.pairs().to_vec()), Some(vec!["ETH/EUR".to_string()]));
+        assert!(ticker.watch("ETH/EUR").is_none());
+
+        // updates for the new pair are kept from now on
+        ticker.stream(frame("update", &[("ETH/EUR", "10", "11")]));
+        assert!(ticker.get("ETH/EUR").is_some());
+    }
+}
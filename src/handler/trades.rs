@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use crate::{
     Kraken, TradeHistoryBody,
@@ -7,6 +7,7 @@ use crate::{
 
 #[derive(Debug, Clone)]
 pub struct Trades {
+    // newest first, like kraken sends them
     pub trades: Vec<Trade>,
     kraken: Arc<Kraken>,
 }
@@ -22,10 +23,33 @@ impl Trades {
         })
     }
 
-    pub async fn update_trades(&mut self) {
-        let body = TradeHistoryBody::default();
-        if let Ok(trades) = self.kraken.get_trades_history(&body).await {
-            self.trades = trades;
-        }
+    // only asks for trades since the newest one held, returns how many were added.
+    // with nothing held yet it takes the latest page like `new`, instead of paging the whole account history
+    pub async fn update_trades(&mut self) -> Result<usize, AuthFetchError> {
+        let Some(newest) = self.trades.first() else {
+            self.trades = self.kraken.get_trades_history(&TradeHistoryBody::default()).await?;
+            return Ok(self.trades.len());
+        };
+
+        let body = TradeHistoryBody {
+            start: Some(newest.time as i64),
+            ..TradeHistoryBody::default()
+        };
+
+        // `start` is in whole seconds, trades in that second come back again
+        let known: HashSet<&str> = self.trades.iter().map(|t| t.id.as_str()).collect();
+        let mut trades: Vec<Trade> = self
+            .kraken
+            .get_all_trades_history(&body)
+            .await?
+            .into_iter()
+            .filter(|t| !known.contains(t.id.as_str()))
+            .collect();
+
+        let added = trades.len();
+        trades.append(&mut self.trades);
+        self.trades = trades;
+
+        Ok(added)
     }
 }
@@ -1,22 +1,51 @@
+pub use crate::auth::nonce::{NoncePrecision, NonceSource};
 pub use crate::auth::token::*;
-pub use crate::fetch::{body::*, kraken::Kraken};
-pub use crate::handler::candle::Candle;
-pub use crate::handler::orderbook::{self, OrderBook};
+pub use crate::fetch::{
+    body::*,
+    error::{AuthFetchError, ErrorCategory, ExchangeError, FetchError},
+    kraken::Kraken,
+    limiter::{Cost, OrderChange, RateLimitError, RateLimiter, RatePolicy, Tier},
+    types::*,
+    urls::Endpoints,
+};
+pub use crate::handler::candle::{Candle, CandleType};
+pub use crate::handler::l3_orderbook::L3OrderBook;
+pub use crate::handler::market_trades::MarketTrades;
+pub use crate::handler::orderbook::{self, Fill, OrderBook, OrderBookError};
+pub use crate::handler::portfolio::{Portfolio, PortfolioError, PortfolioValue};
+pub use crate::handler::ticker::Ticker;
 pub use crate::handler::trades::Trades;
-pub use crate::socket::socket::Incoming;
-pub use crate::socket::{channels::Ch, channels::Channel, socket::Socket};
+pub use crate::socket::{
+    error::{SocketError, WsParseError},
+    events::WsEvent,
+};
+pub use crate::socket::{
+    channels::{BookDepth, Ch, Channel, Subscription},
+    socket::Socket,
+};
+pub use crate::socket::{
+    request::{Pending, RequestError},
+    trading::*,
+};
+pub use crate::types::decimal::{Decimal, DecimalError};
 pub use crate::types::types::*;
 pub use crate::ui::{
     app::{App, Message},
     button::Button,
+    globals::WATCHLIST,
     ui::ui,
 };
 pub use crate::utils::*;
 
+#[cfg(feature = "testkit")]
+pub use crate::testkit::{MOCK_PRIVATE_KEY, MOCK_PUBLIC_KEY, MockKraken, MockServer, fixtures, http::MockRequest};
+
 mod auth;
 mod fetch;
 mod handler;
 mod socket;
+#[cfg(feature = "testkit")]
+mod testkit;
 mod types;
 mod ui;
 mod utils;
@@ -5,10 +5,17 @@ use serde_json::Value;
 
 use crate::types::types::{Params, SubRequest};
 
+const PRIVATE_CHANNELS: [&str; 3] = ["executions", "balances", "level3"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Ch {
     OHLC,
     TICKER,
     BOOK,
+    LEVEL3,
+    TRADE,
+    EXECUTIONS,
+    BALANCES,
 }
 
 impl fmt::Display for Ch {
@@ -17,10 +24,44 @@ impl fmt::Display for Ch {
             Ch::OHLC => write!(f, "ohlc"),
             Ch::TICKER => write!(f, "ticker"),
             Ch::BOOK => write!(f, "book"),
+            Ch::LEVEL3 => write!(f, "level3"),
+            Ch::TRADE => write!(f, "trade"),
+            Ch::EXECUTIONS => write!(f, "executions"),
+            Ch::BALANCES => write!(f, "balances"),
+        }
+    }
+}
+
+// depths kraken accepts for the book channel, the checksum always covers the top 10
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BookDepth {
+    #[default]
+    D10,
+    D25,
+    D100,
+    D500,
+    D1000,
+}
+
+impl BookDepth {
+    pub fn levels(&self) -> usize {
+        match self {
+            BookDepth::D10 => 10,
+            BookDepth::D25 => 25,
+            BookDepth::D100 => 100,
+            BookDepth::D500 => 500,
+            BookDepth::D1000 => 1000,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Subscription {
+    pub channel: String,
+    pub symbol: String,
+}
+
+#[derive(Debug, Clone)]
 pub struct Channel {
     pairs: Vec<String>,
     subscribe: SubRequest,
@@ -34,8 +75,9 @@ impl Channel {
             params: Params {
                 channel: channel.to_string(),
                 symbol: pairs.clone(),
-                extra: extra.unwrap_or(HashMap::new()),
+                extra: extra.unwrap_or_default(),
             },
+            req_id: None,
         };
 
         Self {
@@ -44,7 +86,60 @@ impl Channel {
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.subscribe.params.channel
+    }
+
+    pub fn pairs(&self) -> &[String] {
+        &self.pairs
+    }
+
+    // only accepted on the auth endpoint and with a token
+    pub fn is_private(&self) -> bool {
+        PRIVATE_CHANNELS.contains(&self.name())
+    }
+
+    // copy that carries the session token, the token is never stored so a refreshed one is used after a reconnect
+    pub fn with_token(&self, token: &str) -> Channel {
+        let mut channel = self.clone();
+        channel.subscribe.params.extra.insert("token".into(), Value::String(token.into()));
+        channel
+    }
+
+    // copy whose acks carry `req_id`
+    pub fn with_req_id(&self, req_id: i64) -> Channel {
+        let mut channel = self.clone();
+        channel.subscribe.req_id = Some(req_id);
+        channel
+    }
+
     pub fn subscription(&self) -> String {
         serde_json::to_string(&self.subscribe).unwrap()
     }
+
+    pub fn unsubscription(&self) -> String {
+        let mut unsubscribe = self.subscribe.clone();
+        unsubscribe.method = "unsubscribe".into();
+
+        serde_json::to_string(&unsubscribe).unwrap()
+    }
+
+    // same channel with the same params, the symbols aside
+    pub fn same_params(&self, other: &Channel) -> bool {
+        self.name() == other.name() && self.subscribe.params.extra == other.subscribe.params.extra
+    }
+
+    pub fn add_pairs(&mut self, pairs: &[String]) {
+        for pair in pairs {
+            if !self.pairs.contains(pair) {
+                self.pairs.push(pair.clone());
+                self.subscribe.params.symbol.push(pair.clone());
+            }
+        }
+    }
+
+    pub fn remove_pairs(&mut self, pairs: &[String]) {
+        self.pairs.retain(|p| !pairs.contains(p));
+        self.subscribe.params.symbol.retain(|p| !pairs.contains(p));
+    }
 }
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+use crate::auth::token::GetWebSocketError;
+
+#[derive(Debug, Error)]
+pub enum SocketError {
+    #[error(transparent)]
+    Connection(#[from] tokio_tungstenite::tungstenite::Error),
+
+    #[error(transparent)]
+    Parse(#[from] WsParseError),
+
+    #[error(transparent)]
+    Auth(#[from] GetWebSocketError),
+}
+
+#[derive(Debug, Error)]
+pub enum WsParseError {
+    #[error(transparent)]
+    ParseJSON(#[from] serde_json::Error),
+
+    #[error("Failed to parse {kind} message: {source}")]
+    Message { kind: String, source: serde_json::Error },
+
+    #[error("Unknown message: {0}")]
+    Unknown(String),
+}
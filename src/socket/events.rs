@@ -0,0 +1,96 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    socket::error::WsParseError,
+    types::types::{
+        BalanceData, CandleStick, ExecutionData, KraSoc, L3BookData, MethodError, MethodResponse, OrderBookType, Ping, StatusData, SubscribeAck,
+        TickerType, TradeData,
+    },
+};
+
+const TRADING_METHODS: [&str; 8] = [
+    "add_order",
+    "amend_order",
+    "edit_order",
+    "cancel_order",
+    "cancel_all",
+    "cancel_all_orders_after",
+    "batch_add",
+    "batch_cancel",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsEvent {
+    Book(OrderBookType),
+    Level3(KraSoc<L3BookData>),
+    Ohlc(KraSoc<CandleStick>),
+    Ticker(TickerType),
+    Trade(KraSoc<TradeData>),
+    Executions(KraSoc<ExecutionData>),
+    Balances(KraSoc<BalanceData>),
+    Status(KraSoc<StatusData>),
+    Heartbeat,
+    SubscribeAck(SubscribeAck),
+    UnsubscribeAck(SubscribeAck),
+    Pong(Ping),
+    // answer to add_order, cancel_order and the other trading requests
+    Response(MethodResponse),
+    Error(MethodError),
+    // the connection was re-established and every channel subscribed again
+    Resync,
+}
+
+impl WsEvent {
+    pub fn parse(text: &str) -> Result<Self, WsParseError> {
+        let object: Value = serde_json::from_str(text)?;
+
+        if let Some(method) = object.get("method").and_then(Value::as_str).map(String::from) {
+            if object.get("success").and_then(Value::as_bool) == Some(false) {
+                return Ok(WsEvent::Error(Self::typed(&method, object)?));
+            }
+
+            return match method.as_str() {
+                "subscribe" => Ok(WsEvent::SubscribeAck(Self::typed(&method, object)?)),
+                "unsubscribe" => Ok(WsEvent::UnsubscribeAck(Self::typed(&method, object)?)),
+                "pong" => Ok(WsEvent::Pong(Self::typed(&method, object)?)),
+                method if TRADING_METHODS.contains(&method) => Ok(WsEvent::Response(Self::typed(method, object)?)),
+                _ => Err(WsParseError::Unknown(text.into())),
+            };
+        }
+
+        let channel = object.get("channel").and_then(Value::as_str).map(String::from);
+        match channel.as_deref() {
+            Some("book") => Ok(WsEvent::Book(Self::typed("book", object)?)),
+            Some("level3") => Ok(WsEvent::Level3(Self::typed("level3", object)?)),
+            Some("ohlc") => Ok(WsEvent::Ohlc(Self::typed("ohlc", object)?)),
+            Some("ticker") => Ok(WsEvent::Ticker(Self::typed("ticker", object)?)),
+            Some("trade") => Ok(WsEvent::Trade(Self::typed("trade", object)?)),
+            Some("executions") => Ok(WsEvent::Executions(Self::typed("executions", object)?)),
+            Some("balances") => Ok(WsEvent::Balances(Self::typed("balances", object)?)),
+            Some("status") => Ok(WsEvent::Status(Self::typed("status", object)?)),
+            Some("heartbeat") => Ok(WsEvent::Heartbeat),
+            _ => Err(WsParseError::Unknown(text.into())),
+        }
+    }
+
+    fn typed<T: DeserializeOwned>(kind: &str, object: Value) -> Result<T, WsParseError> {
+        serde_json::from_value(object).map_err(|source| WsParseError::Message {
+            kind: kind.into(),
+            source,
+        })
+    }
+
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            WsEvent::Book(book) => book.data.first().map(|d| d.symbol.as_str()),
+            WsEvent::Level3(book) => book.data.first().map(|d| d.symbol.as_str()),
+            WsEvent::Ohlc(ohlc) => ohlc.data.first().map(|d| d.symbol.as_str()),
+            WsEvent::Ticker(ticker) => ticker.data.first().map(|d| d.symbol.as_str()),
+            WsEvent::Trade(trade) => trade.data.first().map(|d| d.symbol.as_str()),
+            WsEvent::Executions(executions) => executions.data.first().map(|d| d.symbol.as_str()),
+            WsEvent::SubscribeAck(ack) | WsEvent::UnsubscribeAck(ack) => Some(ack.result.symbol.as_str()),
+            _ => None,
+        }
+    }
+}
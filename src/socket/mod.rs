@@ -1,2 +1,6 @@
 pub mod channels;
-pub mod socket;
\ No newline at end of file
+pub mod error;
+pub mod events;
+pub mod request;
+pub mod socket;
+pub mod trading;
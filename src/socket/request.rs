@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicI64, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::{
+    sync::oneshot,
+    time::{self, Sleep},
+};
+
+use crate::{auth::token::GetWebSocketError, fetch::limiter::RateLimitError, types::types::MethodError};
+
+#[derive(Debug, Error)]
+pub enum RequestError {
+    #[error("{} failed: {}", .0.method, .0.error)]
+    Rejected(MethodError),
+
+    #[error("socket is not connected")]
+    NotConnected,
+
+    #[error("trading requests need an authenticated socket")]
+    NotAuthenticated,
+
+    #[error("connection closed before {0} was answered")]
+    Closed(String),
+
+    #[error("{method} was not answered within {after:?}")]
+    Timeout { method: String, after: Duration },
+
+    #[error(transparent)]
+    Auth(#[from] GetWebSocketError),
+
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitError),
+
+    #[error(transparent)]
+    ParseJson(#[from] serde_json::Error),
+}
+
+// what the supervisor hands back for a req_id, the `result` of the ack or the error kraken sent
+pub(crate) type Reply = Result<Value, MethodError>;
+
+struct Waiting {
+    sender: oneshot::Sender<Reply>,
+    // a subscription with several symbols is acked once per symbol, all with the same req_id
+    expected: Option<usize>,
+    results: Vec<Value>,
+}
+
+// hands out req_ids and keeps the callers waiting for an answer.
+// the epoch counts the connections, a frame queued in an older one belongs to a caller that already got Closed
+#[derive(Default)]
+pub(crate) struct Requests {
+    last: AtomicI64,
+    pending: Mutex<(u64, HashMap<i64, Waiting>)>,
+}
+
+impl Requests {
+    pub(crate) fn next_id(&self) -> i64 {
+        self.last.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    // `expected` answers are collected into an array, without it the first answer is the reply
+    pub(crate) fn register<T>(self: &Arc<Self>, method: &str, expected: Option<usize>, timeout: Duration) -> Pending<T> {
+        let req_id = self.next_id();
+        let (sender, reply) = oneshot::channel();
+
+        let waiting = Waiting {
+            sender,
+            expected,
+            results: Vec::new(),
+        };
+        let epoch = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.1.insert(req_id, waiting);
+            pending.0
+        };
+
+        Pending {
+            method: method.into(),
+            req_id,
+            epoch,
+            reply,
+            timeout,
+            deadline: Box::pin(time::sleep(timeout)),
+            requests: Arc::downgrade(self),
+            result: PhantomData,
+        }
+    }
+
+    pub(crate) fn resolve(&self, req_id: i64, reply: Reply) {
+        let (_, pending) = &mut *self.pending.lock().unwrap();
+
+        let Some(waiting) = pending.get_mut(&req_id) else {
+            return;
+        };
+
+        let reply = match (reply, waiting.expected) {
+            (Ok(result), Some(expected)) => {
+                waiting.results.push(result);
+                if waiting.results.len() < expected {
+                    return;
+                }
+                Ok(Value::Array(std::mem::take(&mut waiting.results)))
+            }
+            (reply, _) => reply,
+        };
+
+        if let Some(waiting) = pending.remove(&req_id) {
+            let _ = waiting.sender.send(reply);
+        }
+    }
+
+    pub(crate) fn forget(&self, req_id: i64) {
+        self.pending.lock().unwrap().1.remove(&req_id);
+    }
+
+    pub(crate) fn epoch(&self) -> u64 {
+        self.pending.lock().unwrap().0
+    }
+
+    // dropping the senders fails every waiting caller with RequestError::Closed,
+    // the new epoch keeps their frames that are still queued from being sent
+    pub(crate) fn clear(&self) {
+        let (epoch, pending) = &mut *self.pending.lock().unwrap();
+        pending.clear();
+        *epoch += 1;
+    }
+}
+
+// resolves once the response with the same req_id arrived, or fails after the socket's request timeout
+pub struct Pending<T> {
+    method: String,
+    req_id: i64,
+    epoch: u64,
+    reply: oneshot::Receiver<Reply>,
+    timeout: Duration,
+    deadline: Pin<Box<Sleep>>,
+    requests: Weak<Requests>,
+    result: PhantomData<fn() -> T>,
+}
+
+impl<T> Pending<T> {
+    pub fn req_id(&self) -> i64 {
+        self.req_id
+    }
+
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub(crate) fn epoch(&self) -> u64 {
+        self.epoch
+    }
+}
+
+impl<T: DeserializeOwned> Future for Pending<T> {
+    type Output = Result<T, RequestError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let pending = self.get_mut();
+
+        if let Poll::Ready(reply) = Pin::new(&mut pending.reply).poll(cx) {
+            return Poll::Ready(match reply {
+                Ok(Ok(result)) => serde_json::from_value(result).map_err(RequestError::from),
+                Ok(Err(error)) => Err(RequestError::Rejected(error)),
+                Err(_) => Err(RequestError::Closed(pending.method.clone())),
+            });
+        }
+
+        pending.deadline.as_mut().poll(cx).map(|_| {
+            Err(RequestError::Timeout {
+                method: pending.method.clone(),
+                after: pending.timeout,
+            })
+        })
+    }
+}
+
+// an answer that comes after the caller gave up is not kept around
+impl<T> Drop for Pending<T> {
+    fn drop(&mut self) {
+        if let Some(requests) = self.requests.upgrade() {
+            requests.forget(self.req_id);
+        }
+    }
+}
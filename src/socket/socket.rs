@@ -1,14 +1,19 @@
-use std::{thread, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
 use futures_util::{
     SinkExt, StreamExt, future, pin_mut,
     stream::{SplitSink, SplitStream},
 };
-use serde::Deserializer;
-use serde_json::Value;
+use serde::{Deserializer, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
+    sync::Mutex as AsyncMutex,
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
     task::JoinHandle,
 };
@@ -17,119 +22,521 @@ use tokio_tungstenite::{
     tungstenite::{Error, Message, http::method},
 };
 
-use crate::socket::channels::{self, Channel};
+use crate::{
+    Kraken,
+    auth::token::GetWebSocketError,
+    fetch::{
+        body::{AddOrder, AddOrderBatch, AmendOrder, CancelAllOrdersAfter, CancelOrder, EditOrder},
+        urls::Endpoints,
+    },
+    socket::{
+        channels::{self, Channel, Subscription},
+        error::{SocketError, WsParseError},
+        events::WsEvent,
+        request::{Pending, RequestError, Requests},
+        trading::{
+            WsAddOrderAck, WsAmendOrder, WsAmendOrderAck, WsBatchAdd, WsCancelAfterAck, WsCancelAllAck, WsCancelOrder, WsCancelOrderAck, WsEditOrder,
+            WsEditOrderAck, WsOrder,
+        },
+    },
+    types::types::AckResult,
+};
 
-pub const WEBSOCKET_URL: &str = "wss://ws.kraken.com/v2";
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+// a queued frame with the epoch of Requests it was queued in
+type Frame = (u64, String);
 
-pub struct Socket {
-    channels: Vec<Channel>,
-    read: Option<JoinHandle<()>>,
-    write: Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
-    pub recv_err: Option<UnboundedReceiver<Error>>,
-    pub recv_msg: Option<UnboundedReceiver<Incoming>>,
+#[derive(Serialize)]
+struct Signed<'a, P> {
+    #[serde(flatten)]
+    params: P,
+    token: &'a str,
 }
 
-pub struct Incoming {
-    pub message: Message,
-    pub channel: String,
-    pub pair: String,
+#[derive(Serialize)]
+struct SignedRequest<'a, P> {
+    method: &'a str,
+    params: Signed<'a, P>,
+    req_id: i64,
+}
+
+// a token is fetched again when it has less than this left
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// private channels need a token from GetWebSocketsToken, it is only needed to subscribe
+// so it is fetched lazily and refreshed before it expires
+struct Auth {
+    kraken: Arc<Kraken>,
+    token: AsyncMutex<Option<(String, Instant)>>,
+}
+
+impl Auth {
+    async fn token(&self) -> Result<String, GetWebSocketError> {
+        let mut cached = self.token.lock().await;
+
+        if let Some((token, expires)) = cached.as_ref()
+            && Instant::now() + TOKEN_MARGIN < *expires
+        {
+            return Ok(token.clone());
+        }
+
+        let res = self.kraken.get_websocket_token().await?;
+        let expires = Instant::now() + Duration::from_secs(res.result.expires.max(0) as u64);
+        *cached = Some((res.result.token.clone(), expires));
+
+        Ok(res.result.token)
+    }
+
+    // public channels are sent as they are, also when there is no auth
+    async fn request(auth: Option<&Auth>, channel: &Channel, subscribe: bool) -> Result<String, GetWebSocketError> {
+        let channel = match auth {
+            Some(auth) if channel.is_private() => channel.with_token(&auth.token().await?),
+            _ => channel.clone(),
+        };
+
+        Ok(match subscribe {
+            true => channel.subscription(),
+            false => channel.unsubscription(),
+        })
+    }
+}
+
+// state the supervisor task and the socket both look at
+#[derive(Default)]
+struct Shared {
+    channels: Mutex<Vec<Channel>>,
+    active: Mutex<Vec<Subscription>>,
+    requests: Arc<Requests>,
+}
+
+pub struct Socket {
+    shared: Arc<Shared>,
+    endpoints: Endpoints,
+    backoff: (Duration, Duration),
+    timeout: Duration,
+    auth: Option<Arc<Auth>>,
+    read: Option<JoinHandle<()>>,
+    write: Option<UnboundedSender<Frame>>,
+    send_err: Option<UnboundedSender<SocketError>>,
+    pub recv_err: Option<UnboundedReceiver<SocketError>>,
+    pub recv_msg: Option<UnboundedReceiver<WsEvent>>,
 }
 
 impl Socket {
     pub fn new(channels: Vec<Channel>) -> Self {
+        let shared = Shared {
+            channels: Mutex::new(channels),
+            ..Default::default()
+        };
+
         Self {
-            channels,
+            shared: Arc::new(shared),
+            endpoints: Endpoints::default(),
+            backoff: (Duration::from_millis(500), Duration::from_secs(30)),
+            timeout: REQUEST_TIMEOUT,
+            auth: None,
             read: None,
             write: None,
+            send_err: None,
             recv_err: None,
             recv_msg: None,
         }
     }
 
+    pub fn endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.backoff = (min, max);
+        self
+    }
+
+    // how long subscribe, ping and the trading requests wait for their ack before failing with RequestError::Timeout
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // connects to the auth endpoint and signs private subscriptions with a token from `kraken`
+    pub fn authenticate(mut self, kraken: Arc<Kraken>) -> Self {
+        let auth = Auth {
+            kraken,
+            token: AsyncMutex::new(None),
+        };
+
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    // socket for `executions` and `balances`, using the endpoints of `kraken`
+    pub fn private(kraken: Arc<Kraken>, channels: Vec<Channel>) -> Self {
+        Self::new(channels).endpoints(kraken.get_endpoints().clone()).authenticate(kraken)
+    }
+
+    // socket for `level3`, kraken serves it on its own endpoint
+    pub fn level3(kraken: Arc<Kraken>, channels: Vec<Channel>) -> Self {
+        let endpoints = kraken.get_endpoints().clone();
+        let endpoints = endpoints.clone().ws_private(&endpoints.ws_level3);
+
+        Self::new(channels).endpoints(endpoints).authenticate(kraken)
+    }
+
+    fn url(&self) -> &str {
+        match self.auth {
+            Some(_) => &self.endpoints.ws_private,
+            None => &self.endpoints.ws_public,
+        }
+    }
+
     pub fn print_channels(&mut self) {
-        for channel in self.channels.iter_mut() {
+        for channel in self.shared.channels.lock().unwrap().iter() {
             println!("{}", channel.subscription());
         }
     }
 
     pub async fn start(&mut self) -> Result<(), Error> {
-        let (stream, response) = connect_async(WEBSOCKET_URL).await?;
+        let (stream, response) = connect_async(self.url()).await?;
 
-        let (write, mut read) = stream.split();
+        let (send_write, recv_write) = mpsc::unbounded_channel();
         let (send_err, recv_err) = mpsc::unbounded_channel();
         let (send_msg, recv_msg) = mpsc::unbounded_channel();
 
+        let url = self.url().to_string();
+        let shared = self.shared.clone();
+        let backoff = self.backoff;
+        let auth = self.auth.clone();
+        let errors = send_err.clone();
+
         let read = tokio::spawn(async move {
-            Socket::handle_message(&mut read, send_err, send_msg).await;
+            Socket::supervise(stream, url, shared, backoff, auth, recv_write, errors, send_msg).await;
         });
 
         self.read = Some(read);
-        self.write = Some(write);
+        self.write = Some(send_write);
+        self.send_err = Some(send_err);
         self.recv_err = Some(recv_err);
         self.recv_msg = Some(recv_msg);
 
         Ok(())
     }
 
-    async fn handle_message(read: &mut SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>, send_err: UnboundedSender<Error>, send_msg: UnboundedSender<Incoming>) {
-        while let Some(msg) = read.next().await {
-            match msg {
-                Err(error) => {
-                    let _ = send_err.send(error);
-                }
-                Ok(raw) => {
-                    if let Ok(msg) = Socket::build_message(raw) {
-                        let _ = send_msg.send(msg);
+    // keeps the connection alive, on close or error it backs off, reconnects and subscribes to every channel again
+    #[allow(clippy::too_many_arguments)]
+    async fn supervise(
+        mut stream: WebSocket,
+        url: String,
+        shared: Arc<Shared>,
+        backoff: (Duration, Duration),
+        auth: Option<Arc<Auth>>,
+        mut write: UnboundedReceiver<Frame>,
+        send_err: UnboundedSender<SocketError>,
+        send_msg: UnboundedSender<WsEvent>,
+    ) {
+        loop {
+            if !Socket::handle_message(&mut stream, &shared, &mut write, &send_err, &send_msg).await {
+                return;
+            }
+
+            let mut delay = backoff.0;
+            stream = loop {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(backoff.1);
+
+                let mut stream = match connect_async(&url).await {
+                    Ok((stream, _)) => stream,
+                    Err(error) => {
+                        let _ = send_err.send(error.into());
+                        continue;
+                    }
+                };
+
+                // frames queued while there was no connection are dropped, the stored channels are sent below
+                // and replaying queued subscribes or unsubscribes on top of them would undo or double them
+                while write.try_recv().is_ok() {}
+
+                // answers to requests sent on the old connection will never come, their callers get RequestError::Closed
+                shared.active.lock().unwrap().clear();
+                shared.requests.clear();
+
+                // a channel that could not be subscribed would stay silent on a socket that looks fine,
+                // so a failed token fetch drops the connection and the next attempt backs off like any other
+                match Socket::subscribe_stored(&mut stream, &shared, auth.as_deref()).await {
+                    Ok(()) => break stream,
+                    Err(error) => {
+                        let _ = send_err.send(error);
+                        let _ = stream.close(None).await;
                     }
                 }
+            };
+
+            let _ = send_msg.send(WsEvent::Resync);
+        }
+    }
+
+    async fn subscribe_stored(stream: &mut WebSocket, shared: &Shared, auth: Option<&Auth>) -> Result<(), SocketError> {
+        let channels: Vec<Channel> = shared.channels.lock().unwrap().clone();
+
+        for channel in channels {
+            let channel = channel.with_req_id(shared.requests.next_id());
+            let subscription = Auth::request(auth, &channel, true).await?;
+            stream.send(Message::Text(subscription.into())).await?;
+        }
+
+        Ok(())
+    }
+
+    // returns false once the socket itself has been dropped and there is nothing left to supervise
+    async fn handle_message(
+        stream: &mut WebSocket,
+        shared: &Shared,
+        write: &mut UnboundedReceiver<Frame>,
+        send_err: &UnboundedSender<SocketError>,
+        send_msg: &UnboundedSender<WsEvent>,
+    ) -> bool {
+        loop {
+            tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(Ok(raw)) => match Socket::build_message(raw) {
+                        Ok(Some(event)) => {
+                            Socket::track(shared, &event);
+                            Socket::resolve(shared, &event);
+                            let _ = send_msg.send(event);
+                        }
+                        Ok(None) => {}
+                        Err(error) => {
+                            let _ = send_err.send(error.into());
+                        }
+                    },
+                    Some(Err(error)) => {
+                        let _ = send_err.send(error.into());
+                        return true;
+                    }
+                    None => return true,
+                },
+                out = write.recv() => match out {
+                    // its caller got RequestError::Closed when the connection it was queued for dropped
+                    Some((epoch, _)) if epoch != shared.requests.epoch() => {}
+                    Some((_, message)) => {
+                        if let Err(error) = stream.send(Message::Text(message.into())).await {
+                            let _ = send_err.send(error.into());
+                            return true;
+                        }
+                    }
+                    None => return false,
+                },
             }
         }
     }
 
-    fn build_message(message: Message) -> Result<Incoming, std::io::Error> {
-        let object: Value = serde_json::from_str(&message.to_string())?;
+    fn track(shared: &Shared, event: &WsEvent) {
+        let mut active = shared.active.lock().unwrap();
 
-        let mut channel = String::new();
-        let mut pair = String::new();
+        match event {
+            WsEvent::SubscribeAck(ack) => {
+                let subscription = Subscription {
+                    channel: ack.result.channel.clone(),
+                    symbol: ack.result.symbol.clone(),
+                };
 
-        if let (Some(c), Some(p)) = (object.pointer("/method").and_then(Value::as_str), object.pointer("/result/symbol").and_then(Value::as_str)) {
-            channel = c.to_owned();
-            pair = p.to_owned();
-        } else if let (Some(c), Some(p)) = (object.pointer("/channel").and_then(Value::as_str), object.pointer("/data/0/symbol").and_then(Value::as_str)) {
-            channel = c.to_owned();
-            pair = p.to_owned();
-        } else if let Some(c) = object.pointer("/channel").and_then(Value::as_str) {
-            channel = c.to_owned();
+                if !active.contains(&subscription) {
+                    active.push(subscription);
+                }
+            }
+            WsEvent::UnsubscribeAck(ack) => {
+                active.retain(|s| s.channel != ack.result.channel || s.symbol != ack.result.symbol);
+            }
+            _ => {}
         }
+    }
 
-        Ok(Incoming {
-            message,
-            channel,
-            pair,
-        })
+    // hands acks and errors to the caller waiting on their req_id, the event is still sent on `recv_msg`
+    fn resolve(shared: &Shared, event: &WsEvent) {
+        let (req_id, reply) = match event {
+            WsEvent::Response(response) => (response.req_id, Ok(response.result.clone())),
+            WsEvent::SubscribeAck(ack) | WsEvent::UnsubscribeAck(ack) => (ack.req_id, Ok(json!(ack.result))),
+            WsEvent::Pong(pong) => (Some(pong.req_id), Ok(Value::Null)),
+            WsEvent::Error(error) => (error.req_id, Err(error.clone())),
+            _ => return,
+        };
+
+        if let Some(req_id) = req_id {
+            shared.requests.resolve(req_id, reply);
+        }
+    }
+
+    // control frames are handled by tungstenite, only text frames carry kraken messages
+    fn build_message(message: Message) -> Result<Option<WsEvent>, WsParseError> {
+        match message {
+            Message::Text(text) => WsEvent::parse(&text).map(Some),
+            _ => Ok(None),
+        }
     }
 
     pub async fn send(&mut self, message: &str) {
-        if let Some(mut write) = self.write.as_mut() {
-            write.send(Message::Text(message.into())).await;
+        if let Some(write) = self.write.as_ref() {
+            let _ = write.send((self.shared.requests.epoch(), message.into()));
         }
     }
 
-    pub async fn subscribe_to_channels(&mut self, log:bool) {
-        let channels: Vec<String> = self.channels.iter().map(|c| c.subscription()).collect();
+    // kraken acks every symbol on its own, the Pending resolves once all of them are in
+    async fn send_channel(&self, channel: &Channel, subscribe: bool) -> Result<Pending<Vec<AckResult>>, RequestError> {
+        let write = self.write.as_ref().ok_or(RequestError::NotConnected)?;
+
+        let method = match subscribe {
+            true => "subscribe",
+            false => "unsubscribe",
+        };
+
+        let pending = self.shared.requests.register(method, Some(channel.pairs().len().max(1)), self.timeout);
+        let request = Auth::request(self.auth.as_deref(), &channel.with_req_id(pending.req_id()), subscribe).await?;
+        write.send((pending.epoch(), request)).map_err(|_| RequestError::NotConnected)?;
+
+        Ok(pending)
+    }
+
+    // does not wait for the acks, a token that could not be fetched is reported on `recv_err`
+    pub async fn subscribe_to_channels(&mut self, log: bool) {
+        let channels: Vec<Channel> = self.shared.channels.lock().unwrap().clone();
         for channel in channels {
             if log {
-                println!("Subscribe -> {}", channel);
+                println!("Subscribe -> {}", channel.subscription());
+            }
+
+            if let Err(RequestError::Auth(error)) = self.send_channel(&channel, true).await
+                && let Some(send_err) = self.send_err.as_ref()
+            {
+                let _ = send_err.send(error.into());
             }
-            self.send(&channel).await;
         }
     }
 
+    // stored so it is subscribed again after a reconnect, `active_subscriptions` lists it once kraken acked it.
+    // a channel already stored with the same params only gains the new pairs, so a reconnect never sends duplicates
+    pub async fn subscribe(&mut self, channel: Channel) -> Result<Pending<Vec<AckResult>>, RequestError> {
+        let pending = self.send_channel(&channel, true).await;
+
+        let mut channels = self.shared.channels.lock().unwrap();
+        match channels.iter_mut().find(|c| c.same_params(&channel)) {
+            Some(stored) => stored.add_pairs(channel.pairs()),
+            None => channels.push(channel),
+        }
+
+        pending
+    }
+
+    // only the stored channel with the same params loses the pairs, a book at another depth stays subscribed
+    pub async fn unsubscribe(&mut self, channel: Channel) -> Result<Pending<Vec<AckResult>>, RequestError> {
+        let pending = self.send_channel(&channel, false).await;
+
+        let mut channels = self.shared.channels.lock().unwrap();
+        for stored in channels.iter_mut().filter(|c| c.same_params(&channel)) {
+            stored.remove_pairs(channel.pairs());
+        }
+
+        // private channels have no pairs to run out of
+        channels.retain(|c| match c.is_private() {
+            true => !c.same_params(&channel),
+            false => !c.pairs().is_empty(),
+        });
+
+        pending
+    }
+
+    // asks kraken for a fresh snapshot without touching the stored channels, resolves with the subscribe acks
+    pub async fn resubscribe(&mut self, channel: &Channel) -> Result<Pending<Vec<AckResult>>, RequestError> {
+        self.send_channel(channel, false).await?;
+        self.send_channel(channel, true).await
+    }
+
+    // resolves with the matching pong
+    pub async fn ping(&self) -> Result<Pending<()>, RequestError> {
+        let write = self.write.as_ref().ok_or(RequestError::NotConnected)?;
+
+        let pending = self.shared.requests.register("ping", None, self.timeout);
+        let message = json!({ "method": "ping", "req_id": pending.req_id() });
+        write.send((pending.epoch(), message.to_string())).map_err(|_| RequestError::NotConnected)?;
+
+        Ok(pending)
+    }
+
+    pub fn channels(&self) -> Vec<Channel> {
+        self.shared.channels.lock().unwrap().clone()
+    }
+
+    pub fn active_subscriptions(&self) -> Vec<Subscription> {
+        self.shared.active.lock().unwrap().clone()
+    }
+
+    // signs `params` with the session token and sends them under a fresh req_id.
+    // written straight to text, a detour through Value would turn the exact prices back into f64
+    async fn request<P: Serialize, T: DeserializeOwned>(&self, method: &str, params: P) -> Result<Pending<T>, RequestError> {
+        let auth = self.auth.as_deref().ok_or(RequestError::NotAuthenticated)?;
+        let write = self.write.as_ref().ok_or(RequestError::NotConnected)?;
+
+        let token = auth.token().await?;
+        let pending = self.shared.requests.register(method, None, self.timeout);
+
+        let message = SignedRequest {
+            method,
+            params: Signed {
+                params,
+                token: &token,
+            },
+            req_id: pending.req_id(),
+        };
+        let message = serde_json::to_string(&message)?;
+        write.send((pending.epoch(), message)).map_err(|_| RequestError::NotConnected)?;
+
+        Ok(pending)
+    }
+
+    // kraken counts orders per pair across rest and the socket, so both charge the same limiter
+    async fn acquire_order(&self, pair: &str, cost: f64) -> Result<(), RequestError> {
+        let auth = self.auth.as_deref().ok_or(RequestError::NotAuthenticated)?;
+        Ok(auth.kraken.acquire_order(pair, cost).await?)
+    }
+
+    // awaiting the call sends the request, awaiting the returned Pending waits for kraken's answer
+    pub async fn add_order(&self, order: &AddOrder) -> Result<Pending<WsAddOrderAck>, RequestError> {
+        self.acquire_order(&order.pair, 1.).await?;
+        self.request("add_order", WsOrder::from(order)).await
+    }
+
+    pub async fn batch_add(&self, batch: &AddOrderBatch) -> Result<Pending<Vec<WsAddOrderAck>>, RequestError> {
+        self.acquire_order(&batch.pair, batch.orders.len() as f64).await?;
+        self.request("batch_add", WsBatchAdd::from(batch)).await
+    }
+
+    pub async fn amend_order(&self, amend: &AmendOrder) -> Result<Pending<WsAmendOrderAck>, RequestError> {
+        self.request("amend_order", WsAmendOrder::from(amend)).await
+    }
+
+    pub async fn edit_order(&self, edit: &EditOrder) -> Result<Pending<WsEditOrderAck>, RequestError> {
+        self.request("edit_order", WsEditOrder::from(edit)).await
+    }
+
+    pub async fn cancel_order(&self, cancel: &CancelOrder) -> Result<Pending<WsCancelOrderAck>, RequestError> {
+        self.request("cancel_order", WsCancelOrder::from(cancel)).await
+    }
+
+    pub async fn cancel_all(&self) -> Result<Pending<WsCancelAllAck>, RequestError> {
+        self.request("cancel_all", json!({})).await
+    }
+
+    // kraken's dead man's switch, v2 names it cancel_all_orders_after
+    pub async fn cancel_on_disconnect(&self, after: &CancelAllOrdersAfter) -> Result<Pending<WsCancelAfterAck>, RequestError> {
+        self.request("cancel_all_orders_after", after).await
+    }
+
     pub async fn stop(&mut self) {
         if let Some(read) = self.read.as_mut() {
             read.abort();
         }
 
         self.read = None;
+        self.write = None;
     }
 }
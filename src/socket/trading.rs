@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize, Serializer, ser::Error};
+use serde_json::value::RawValue;
+
+use crate::{
+    fetch::body::{AddOrder, AddOrderBatch, AmendOrder, BatchOrder, CancelOrder, EditOrder, OrderType, Side, StpType, TimeInForce},
+    types::decimal::Decimal,
+};
+
+// the socket takes numbers where rest takes strings, written with every digit instead of going through f64
+fn number<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    RawValue::from_string(value.to_string()).map_err(S::Error::custom)?.serialize(serializer)
+}
+
+fn optional_number<S: Serializer>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => number(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WsTriggers {
+    // `last` or `index`
+    pub reference: String,
+    #[serde(serialize_with = "number")]
+    pub price: Decimal,
+}
+
+// one order in v2 naming, built from the rest AddOrder and BatchOrder so both transports share one model
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct WsOrder {
+    pub order_type: OrderType,
+    pub side: Side,
+    #[serde(serialize_with = "number")]
+    pub order_qty: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub limit_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triggers: Option<WsTriggers>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub display_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduce_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_preference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_mpp: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stp_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_userref: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expire_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate: Option<bool>,
+}
+
+impl WsOrder {
+    // rest puts the trigger in `price` and the limit in `price2` for stop and take profit orders
+    fn prices(
+        order_type: OrderType,
+        price: Option<Decimal>,
+        price2: Option<Decimal>,
+        trigger: Option<&str>,
+    ) -> (Option<Decimal>, Option<WsTriggers>) {
+        let triggered = matches!(
+            order_type,
+            OrderType::StopLoss
+                | OrderType::StopLossLimit
+                | OrderType::TakeProfit
+                | OrderType::TakeProfitLimit
+                | OrderType::TrailingStop
+                | OrderType::TrailingStopLimit
+        );
+
+        if !triggered {
+            return (price, None);
+        }
+
+        let triggers = price.map(|price| WsTriggers {
+            reference: trigger.unwrap_or("last").to_string(),
+            price,
+        });
+
+        (price2, triggers)
+    }
+
+    // post, fcib, fciq and nompp are flags in rest and fields on the socket
+    fn flags(&mut self, oflags: Option<&str>) {
+        for flag in oflags.unwrap_or_default().split(',') {
+            match flag.trim() {
+                "post" => self.post_only = Some(true),
+                "fcib" => self.fee_preference = Some("base".into()),
+                "fciq" => self.fee_preference = Some("quote".into()),
+                "nompp" => self.no_mpp = Some(true),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl From<&BatchOrder> for WsOrder {
+    fn from(order: &BatchOrder) -> Self {
+        let (limit_price, triggers) = Self::prices(order.ordertype, order.price, order.price2, order.trigger.as_deref());
+
+        let time_in_force = order.time_in_force.map(|tif| match tif {
+            TimeInForce::GTC => "gtc".to_string(),
+            TimeInForce::IOC => "ioc".to_string(),
+            TimeInForce::GTD => "gtd".to_string(),
+        });
+
+        let stp_type = order.stp_type.map(|stp| match stp {
+            StpType::CancelNewest => "cancel_newest".to_string(),
+            StpType::CancelOldest => "cancel_oldest".to_string(),
+            StpType::CancelBoth => "cancel_both".to_string(),
+        });
+
+        let mut ws_order = WsOrder {
+            order_type: order.ordertype,
+            side: order.type_field,
+            order_qty: order.volume,
+            limit_price,
+            triggers,
+            display_qty: order.display_vol,
+            time_in_force,
+            reduce_only: order.reduce_only,
+            margin: order.leverage.as_ref().map(|_| true),
+            stp_type,
+            cl_ord_id: order.cl_ord_id.clone(),
+            order_userref: order.user_ref,
+            effective_time: order.start_time.clone(),
+            expire_time: order.expire_time.clone(),
+            ..WsOrder::default()
+        };
+
+        ws_order.flags(order.of_lags.as_deref());
+        ws_order
+    }
+}
+
+impl From<&AddOrder> for WsOrder {
+    fn from(order: &AddOrder) -> Self {
+        let batch_order = BatchOrder {
+            ordertype: order.ordertype,
+            type_field: order.type_field,
+            volume: order.volume,
+            cl_ord_id: order.cl_ord_id.clone(),
+            price: order.price,
+            user_ref: order.user_ref,
+            display_vol: order.display_vol,
+            price2: order.price2,
+            trigger: order.trigger.clone(),
+            leverage: order.leverage.clone(),
+            reduce_only: order.reduce_only,
+            stp_type: order.stp_type,
+            of_lags: order.of_lags.clone(),
+            time_in_force: order.time_in_force,
+            start_time: order.start_time.clone(),
+            expire_time: order.expire_time.clone(),
+        };
+
+        WsOrder {
+            symbol: Some(order.pair.clone()),
+            deadline: order.deadline.clone(),
+            validate: order.validate,
+            ..WsOrder::from(&batch_order)
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct WsBatchAdd {
+    pub symbol: String,
+    pub orders: Vec<WsOrder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate: Option<bool>,
+}
+
+impl From<&AddOrderBatch> for WsBatchAdd {
+    fn from(batch: &AddOrderBatch) -> Self {
+        WsBatchAdd {
+            symbol: batch.pair.clone(),
+            orders: batch.orders.iter().map(WsOrder::from).collect(),
+            deadline: batch.deadline.clone(),
+            validate: batch.validate,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct WsAmendOrder {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub order_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub display_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub limit_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub trigger_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+}
+
+impl From<&AmendOrder> for WsAmendOrder {
+    fn from(amend: &AmendOrder) -> Self {
+        WsAmendOrder {
+            order_id: amend.txid.clone(),
+            cl_ord_id: amend.cl_ord_id.clone(),
+            order_qty: amend.order_qty,
+            display_qty: amend.display_qty,
+            limit_price: amend.limit_price,
+            trigger_price: amend.trigger_price,
+            post_only: amend.post_only,
+            deadline: amend.deadline.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct WsEditOrder {
+    pub order_id: String,
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_userref: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub order_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub display_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "optional_number")]
+    pub limit_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate: Option<bool>,
+}
+
+impl From<&EditOrder> for WsEditOrder {
+    fn from(edit: &EditOrder) -> Self {
+        WsEditOrder {
+            order_id: edit.txid.clone(),
+            symbol: edit.pair.clone(),
+            order_userref: edit.userref,
+            order_qty: edit.volume,
+            display_qty: edit.display_vol,
+            limit_price: edit.price,
+            post_only: edit.of_lags.as_deref().map(|flags| flags.split(',').any(|f| f.trim() == "post")),
+            deadline: edit.deadline.clone(),
+            validate: edit.validate,
+        }
+    }
+}
+
+// v2 cancels take lists, rest takes a single id
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct WsCancelOrder {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub order_id: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cl_ord_id: Vec<String>,
+}
+
+impl From<&CancelOrder> for WsCancelOrder {
+    fn from(cancel: &CancelOrder) -> Self {
+        WsCancelOrder {
+            order_id: cancel.txid.iter().cloned().collect(),
+            cl_ord_id: cancel.cl_ord_id.iter().cloned().collect(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WsAddOrderAck {
+    pub order_id: String,
+    #[serde(default)]
+    pub cl_ord_id: Option<String>,
+    #[serde(default)]
+    pub order_userref: Option<i64>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WsAmendOrderAck {
+    pub amend_id: String,
+    #[serde(default)]
+    pub order_id: Option<String>,
+    #[serde(default)]
+    pub cl_ord_id: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WsEditOrderAck {
+    pub order_id: String,
+    pub original_order_id: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WsCancelOrderAck {
+    #[serde(default)]
+    pub order_id: Option<String>,
+    #[serde(default)]
+    pub cl_ord_id: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WsCancelAllAck {
+    pub count: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsCancelAfterAck {
+    pub current_time: String,
+    pub trigger_time: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_keep_every_digit() {
+        let order = AddOrder {
+            ordertype: OrderType::Limit,
+            pair: "BTC/EUR".into(),
+            volume: "1.23456789012345678".parse().unwrap(),
+            price: Some("0.00012300".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&WsOrder::from(&order)).unwrap();
+        assert!(json.contains(r#""order_qty":1.23456789012345678"#), "{json}");
+        assert!(json.contains(r#""limit_price":0.00012300"#), "{json}");
+    }
+}
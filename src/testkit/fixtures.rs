@@ -0,0 +1,741 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Value, json};
+
+use crate::{
+    fetch::types::AssetPairs,
+    types::{decimal::Decimal, types::CandleStick},
+    utils::epoch_to_rfc3339,
+};
+
+// every generated candle is anchored here so REST and socket data line up
+pub const MOCK_EPOCH: u64 = 1_700_000_000;
+pub const MOCK_CANDLES: usize = 50;
+pub const MOCK_HISTORY: usize = 120;
+const HISTORY_PAGE: usize = 50;
+pub const MOCK_TOKEN: &str = "mock-websocket-token";
+
+pub const PRICE_DECIMALS: i32 = 1;
+pub const QTY_DECIMALS: i32 = 8;
+
+pub fn ok(result: Value) -> Value {
+    json!({ "error": [], "result": result })
+}
+
+pub fn err(errors: &[&str]) -> Value {
+    json!({ "error": errors, "result": {} })
+}
+
+pub fn now_rfc3339() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+pub fn server_time() -> Value {
+    let now = Utc::now();
+    json!({
+        "unixtime": now.timestamp(),
+        "rfc1123": now.format("%a, %d %b %y %H:%M:%S +0000").to_string(),
+    })
+}
+
+pub fn asset_pair(pair: &str) -> AssetPairs {
+    let (base, quote) = pair.split_once('/').unwrap_or((pair, "EUR"));
+
+    AssetPairs {
+        altname: format!("{}{}", base, quote),
+        wsname: pair.into(),
+        aclass_base: "currency".into(),
+        base: base.into(),
+        aclass_quote: "currency".into(),
+        quote: quote.into(),
+        lot: "unit".into(),
+        cost_decimals: 5,
+        pair_decimals: PRICE_DECIMALS,
+        lot_decimals: QTY_DECIMALS,
+        lot_multiplier: 1,
+        leverage_buy: vec![2, 3],
+        leverage_sell: vec![2, 3],
+        fees: vec![vec![0., 0.4], vec![50000., 0.35]],
+        fees_maker: vec![vec![0., 0.25], vec![50000., 0.2]],
+        fee_volume_currency: "ZUSD".into(),
+        margin_call: 80,
+        margin_stop: 40,
+        ordermin: "0.0001".into(),
+        costmin: "0.5".into(),
+        tick_size: "0.1".into(),
+        status: "online".into(),
+        long_position_limit: 250,
+        short_position_limit: 200,
+    }
+}
+
+pub fn candles(pair: &str, interval: i64, count: usize) -> Vec<CandleStick> {
+    let step = interval as u64 * 60;
+    let last = MOCK_EPOCH - MOCK_EPOCH % step;
+
+    (0..count)
+        .map(|i| {
+            let epoch = last - (count - 1 - i) as u64 * step;
+            let open = 40000. + (i as f64 * 7.) % 300.;
+            let close = open + if i % 3 == 0 { -25. } else { 40. };
+
+            CandleStick {
+                symbol: pair.into(),
+                open: decimal(open),
+                high: decimal(open.max(close) + 12.5),
+                low: decimal(open.min(close) - 8.),
+                close: decimal(close),
+                trades: 10 + i as i64,
+                volume: decimal(1.5 + i as f64 / 10.),
+                epoch,
+                vwap: decimal((open + close) / 2.),
+                interval_begin: epoch_to_rfc3339(epoch),
+                interval,
+                timestamp: epoch_to_rfc3339(epoch + step),
+            }
+        })
+        .collect()
+}
+
+pub fn ohlc(pair: &str, interval: i64) -> Value {
+    let sticks: Vec<Value> = candles(pair, interval, MOCK_CANDLES)
+        .iter()
+        .map(|c| {
+            json!([
+                c.epoch,
+                c.open.to_string(),
+                c.high.to_string(),
+                c.low.to_string(),
+                c.close.to_string(),
+                c.vwap.to_string(),
+                c.volume.to_string(),
+                c.trades
+            ])
+        })
+        .collect();
+
+    let last = candles(pair, interval, 1)[0].epoch;
+    json!({ pair: sticks, "last": last })
+}
+
+pub fn ohlc_snapshot(pair: &str, interval: i64) -> Value {
+    json!({
+        "channel": "ohlc",
+        "type": "snapshot",
+        "timestamp": now_rfc3339(),
+        "data": candles(pair, interval, MOCK_CANDLES).iter().map(socket_candle).collect::<Vec<Value>>(),
+    })
+}
+
+// the socket sends numbers where REST sends strings
+fn socket_candle(candle: &CandleStick) -> Value {
+    let mut value = json!(candle);
+    for key in ["open", "high", "low", "close", "volume", "vwap"] {
+        value[key] = json!(value[key].as_str().and_then(|v| v.parse::<f64>().ok()));
+    }
+    value
+}
+
+// a trade every 30 seconds up to MOCK_EPOCH, paged like kraken with at most 1000 per call
+pub fn trades(pair: &str, since: &str) -> Value {
+    let since = (since.parse::<u128>().unwrap_or(0) / 1_000_000_000) as u64;
+    let first = since - since % 30 + 30;
+
+    let times: Vec<u64> = (first..MOCK_EPOCH).step_by(30).take(1000).collect();
+    let trades: Vec<Value> = times
+        .iter()
+        .map(|time| {
+            let price = 40000. + (time / 30 % 300) as f64 / 10.;
+            let side = if time / 30 % 2 == 0 { "b" } else { "s" };
+            json!([format!("{:.1}", price), "0.01000000", *time as f64, side, "m", "", time / 30])
+        })
+        .collect();
+
+    let last = match times.last() {
+        Some(time) => (*time as u128 * 1_000_000_000).to_string(),
+        None => (since as u128 * 1_000_000_000).to_string(),
+    };
+
+    json!({ pair: trades, "last": last })
+}
+
+// rest ticker, same numbers as the socket `ticker` below
+pub fn ticker_info() -> Value {
+    json!({
+        "a": ["40100.1", "1", "1.250"],
+        "b": ["40100.0", "1", "0.500"],
+        "c": ["40100.1", "0.01000000"],
+        "v": ["400.50000000", "1234.50000000"],
+        "p": ["40060.1", "40050.2"],
+        "t": [5120, 18234],
+        "l": ["39900.0", "39800.0"],
+        "h": ["40300.0", "40400.0"],
+        "o": "39980.0",
+    })
+}
+
+// levels a tick apart around 40100, in the order kraken sends them
+pub fn depth(count: usize) -> Value {
+    let level = |price: f64, i: usize| json!([format!("{:.1}", price), format!("{:.8}", 0.1 + i as f64 / 100.), MOCK_EPOCH]);
+
+    json!({
+        "asks": (0..count).map(|i| level(40100.1 + i as f64 / 10., i)).collect::<Vec<Value>>(),
+        "bids": (0..count).map(|i| level(40100.0 - i as f64 / 10., i)).collect::<Vec<Value>>(),
+    })
+}
+
+pub fn spread(pair: &str) -> Value {
+    let spreads: Vec<Value> = (0..5).map(|i| json!([MOCK_EPOCH + i, "40100.0", "40100.1"])).collect();
+    json!({ pair: spreads, "last": MOCK_EPOCH + 4 })
+}
+
+pub fn assets() -> Value {
+    json!({
+        "XXBT": { "aclass": "currency", "altname": "XBT", "decimals": 10, "display_decimals": 5, "collateral_value": 1.0, "status": "enabled" },
+        "ZEUR": { "aclass": "currency", "altname": "EUR", "decimals": 4, "display_decimals": 2, "collateral_value": 1.0, "status": "enabled" },
+        "XETH": { "aclass": "currency", "altname": "ETH", "decimals": 10, "display_decimals": 5, "collateral_value": 1.0, "status": "enabled" },
+    })
+}
+
+pub fn system_status() -> Value {
+    json!({ "status": "online", "timestamp": epoch_to_rfc3339(MOCK_EPOCH) })
+}
+
+pub fn ticker(pair: &str) -> Value {
+    json!({
+        "symbol": pair,
+        "bid": 40100.0,
+        "bid_qty": 0.5,
+        "ask": 40100.1,
+        "ask_qty": 1.25,
+        "last": 40100.1,
+        "volume": 1234.5,
+        "vwap": 40050.2,
+        "low": 39800.0,
+        "high": 40400.0,
+        "change": 120.3,
+        "change_pct": 0.3,
+        "volume_usd": 49500000.0,
+        "timestamp": now_rfc3339(),
+    })
+}
+
+pub fn ticker_snapshot(pair: &str) -> Value {
+    json!({ "channel": "ticker", "type": "snapshot", "data": [ticker(pair)] })
+}
+
+// one print on the `trade` channel, buys tick up from 40100 and sells tick down
+pub fn trade(pair: &str, trade_id: i64, side: &str) -> Value {
+    let step = if side == "buy" { 0.1 } else { -0.1 };
+
+    json!({
+        "symbol": pair,
+        "side": side,
+        "price": 40100.0 + step * (trade_id % 10) as f64,
+        "qty": 0.01 * (trade_id % 5 + 1) as f64,
+        "ord_type": if trade_id % 3 == 0 { "limit" } else { "market" },
+        "trade_id": trade_id,
+        "timestamp": epoch_to_rfc3339(MOCK_EPOCH + trade_id as u64),
+    })
+}
+
+// the last 50 prints, oldest first like kraken
+pub fn trade_snapshot(pair: &str) -> Value {
+    let trades: Vec<Value> = (1..=50).map(|id| trade(pair, id, if id % 2 == 0 { "buy" } else { "sell" })).collect();
+    json!({ "channel": "trade", "type": "snapshot", "data": trades })
+}
+
+pub fn trade_update(pair: &str, trade_id: i64, side: &str) -> Value {
+    json!({ "channel": "trade", "type": "update", "data": [trade(pair, trade_id, side)] })
+}
+
+pub fn balance() -> Value {
+    json!({ "ZEUR": "1000.0000", "XXBT": "0.5000000000" })
+}
+
+pub fn balance_ex() -> Value {
+    json!({
+        "ZEUR": { "balance": "1000.0000", "credit": "0.0000", "credit_used": "0.0000", "hold_trade": "0.0000" },
+        "XXBT": { "balance": "0.5000000000", "credit": "0.0000", "credit_used": "0.0000", "hold_trade": "0.0000" },
+    })
+}
+
+pub fn trade_balance() -> Value {
+    json!({
+        "eb": "21000.0000", "tb": "1000.0000", "m": "0.0000", "uv": "0.0000", "n": "0.0000",
+        "c": "0.0000", "v": "0.0000", "e": "1000.0000", "mf": "1000.0000", "ml": "0.0000", "mfo": "1000.0000",
+    })
+}
+
+// MOCK_HISTORY trades a minute apart, newest first, paged by 50 like kraken
+pub fn trades_history(ofs: usize, start: Option<u64>) -> Value {
+    let trades: Vec<(String, Value)> = (0..MOCK_HISTORY)
+        .map(|i| (i, MOCK_EPOCH - i as u64 * 60))
+        .filter(|(_, time)| start.is_none_or(|start| *time > start))
+        .map(|(i, time)| (format!("THMOCK-{:05}-TRADE", i), history_trade(i, time)))
+        .collect();
+
+    json!({
+        "count": trades.len(),
+        "trades": page(trades, ofs),
+    })
+}
+
+fn history_trade(i: usize, time: u64) -> Value {
+    json!({
+        "ordertxid": format!("OQMOCK-{:05}-ORDER", i),
+        "postxid": "TKH2SE-M7IF5-CFI7LT",
+        "pair": "XXBTZEUR",
+        "aclass": "forex",
+        "time": time as f64 + 0.1234,
+        "type": if i.is_multiple_of(2) { "buy" } else { "sell" },
+        "ordertype": "limit",
+        "tradeordertype": "limit",
+        "price": "40000.00000",
+        "cost": "400.00000",
+        "fee": "1.04000",
+        "vol": "0.01000000",
+        "margin": "0.00000",
+        "leverage": "0",
+        "misc": "",
+        "trade_id": 93748276 + i,
+        "maker": true,
+        "ledgers": [format!("LMOCK-{:05}-LEDGR", i)],
+    })
+}
+
+pub fn ledgers(ofs: usize) -> Value {
+    let entries: Vec<(String, Value)> = (0..MOCK_HISTORY).map(|i| (format!("LMOCK-{:05}-LEDGR", i), ledger_entry(i))).collect();
+
+    json!({
+        "count": entries.len(),
+        "ledger": page(entries, ofs),
+    })
+}
+
+pub fn query_ledgers(ids: &str) -> Value {
+    let entries: serde_json::Map<String, Value> = (0..MOCK_HISTORY)
+        .map(|i| (format!("LMOCK-{:05}-LEDGR", i), ledger_entry(i)))
+        .filter(|(id, _)| ids.split(',').any(|wanted| wanted == id))
+        .collect();
+
+    Value::Object(entries)
+}
+
+fn ledger_entry(i: usize) -> Value {
+    json!({
+        "refid": format!("THMOCK-{:05}-TRADE", i),
+        "time": (MOCK_EPOCH - i as u64 * 60) as f64 + 0.1234,
+        "type": "trade",
+        "subtype": "",
+        "aclass": "currency",
+        "asset": "ZEUR",
+        "amount": "-400.0000",
+        "fee": "1.0400",
+        "balance": "1000.0000",
+    })
+}
+
+fn page(items: Vec<(String, Value)>, ofs: usize) -> serde_json::Map<String, Value> {
+    items.into_iter().skip(ofs).take(HISTORY_PAGE).collect()
+}
+
+pub fn add_order() -> Value {
+    json!({
+        "descr": { "order": "buy 0.01000000 XBTEUR @ market" },
+        "txid": ["OUF4EM-FRGI2-MQMWZD"],
+    })
+}
+
+pub fn order(status: &str) -> Value {
+    let closed = status == "closed";
+
+    json!({
+        "refid": null,
+        "userref": 0,
+        "status": status,
+        "opentm": MOCK_EPOCH as f64,
+        "starttm": 0,
+        "expiretm": 0,
+        "closetm": if closed { json!(MOCK_EPOCH as f64 + 1.) } else { Value::Null },
+        "descr": {
+            "pair": "XBTEUR",
+            "type": "buy",
+            "ordertype": "limit",
+            "price": "40000.0",
+            "price2": "0",
+            "leverage": "none",
+            "order": "buy 0.01000000 XBTEUR @ limit 40000.0",
+            "close": "",
+        },
+        "vol": "0.01000000",
+        "vol_exec": if closed { "0.01000000" } else { "0.00000000" },
+        "cost": if closed { "400.00000" } else { "0.00000" },
+        "fee": if closed { "1.04000" } else { "0.00000" },
+        "price": if closed { "40000.0" } else { "0.00000" },
+        "stopprice": "0.00000",
+        "limitprice": "0.00000",
+        "misc": "",
+        "oflags": "fciq",
+    })
+}
+
+pub fn open_orders() -> Value {
+    json!({ "open": { "OQCLML-BW3P3-BUCMWZ": order("open") } })
+}
+
+pub fn closed_orders() -> Value {
+    json!({ "closed": { "OUF4EM-FRGI2-MQMWZD": order("closed") }, "count": 1 })
+}
+
+pub fn query_orders(txids: &str) -> Value {
+    txids.split(',').map(|txid| (txid.to_string(), order("closed"))).collect()
+}
+
+pub fn add_order_batch(count: usize) -> Value {
+    let orders: Vec<Value> = (0..count)
+        .map(|i| json!({ "descr": { "order": "buy 0.01000000 XBTEUR @ limit 40000.0" }, "txid": format!("OBATCH-{:05}-MOCK", i) }))
+        .collect();
+
+    json!({ "orders": orders })
+}
+
+pub fn edit_order(txid: &str) -> Value {
+    json!({
+        "status": "ok",
+        "txid": "OFVXHJ-KPQ3B-VS7ELA",
+        "originaltxid": txid,
+        "volume": "0.01000000",
+        "price": "40100.0",
+        "orders_cancelled": 1,
+        "descr": { "order": "buy 0.01000000 XBTEUR @ limit 40100.0" },
+    })
+}
+
+pub fn amend_order() -> Value {
+    json!({ "amend_id": "TTW6PD-RC36L-ZZSWNU" })
+}
+
+pub fn cancel_order() -> Value {
+    json!({ "count": 1 })
+}
+
+pub fn cancel_all() -> Value {
+    json!({ "count": 2 })
+}
+
+pub fn cancel_all_after(timeout: u64) -> Value {
+    let trigger = if timeout == 0 {
+        epoch_to_rfc3339(0)
+    } else {
+        epoch_to_rfc3339(MOCK_EPOCH + timeout)
+    };
+    json!({ "currentTime": epoch_to_rfc3339(MOCK_EPOCH), "triggerTime": trigger })
+}
+
+pub fn websocket_token() -> Value {
+    json!({ "token": MOCK_TOKEN, "expires": 900 })
+}
+
+pub fn ws_add_order(params: &Value) -> Value {
+    json!({ "order_id": "OUF4EM-FRGI2-MQMWZD", "cl_ord_id": params["cl_ord_id"], "order_userref": params["order_userref"] })
+}
+
+// one resting limit order, the same one OpenOrders returns
+pub fn execution(exec_type: &str) -> Value {
+    json!({
+        "order_id": "OUF4EM-FRGI2-MQMWZD",
+        "exec_type": exec_type,
+        "symbol": "BTC/EUR",
+        "side": "buy",
+        "order_type": "limit",
+        "order_status": if exec_type == "trade" { "partially_filled" } else { "new" },
+        "order_qty": 0.01,
+        "limit_price": 40000.0,
+        "cum_qty": 0.0,
+        "cum_cost": 0.0,
+        "avg_price": 0.0,
+        "fees": [],
+        "order_userref": 0,
+        "timestamp": now_rfc3339(),
+    })
+}
+
+pub fn executions_snapshot() -> Value {
+    json!({ "channel": "executions", "type": "snapshot", "data": [execution("new")], "sequence": 1 })
+}
+
+pub fn balances_snapshot() -> Value {
+    json!({
+        "channel": "balances",
+        "type": "snapshot",
+        "data": [
+            { "asset": "EUR", "asset_class": "currency", "balance": 1000.0, "wallets": [{ "type": "spot", "id": "main", "balance": 1000.0 }] },
+            { "asset": "BTC", "asset_class": "currency", "balance": 0.5, "wallets": [{ "type": "spot", "id": "main", "balance": 0.5 }] },
+        ],
+        "sequence": 1,
+    })
+}
+
+// keeps the book the same way kraken does, so scripted frames carry valid checksums
+#[derive(Debug, Clone)]
+pub struct BookScript {
+    symbol: String,
+    price_decimals: i32,
+    qty_decimals: i32,
+    depth: usize,
+    asks: BTreeMap<i64, i64>,
+    bids: BTreeMap<Reverse<i64>, i64>,
+}
+
+impl BookScript {
+    pub fn new(symbol: &str, price_decimals: i32, qty_decimals: i32) -> Self {
+        Self {
+            symbol: symbol.into(),
+            price_decimals,
+            qty_decimals,
+            depth: 10,
+            asks: BTreeMap::new(),
+            bids: BTreeMap::new(),
+        }
+    }
+
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn snapshot(&mut self, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> Value {
+        self.asks.clear();
+        self.bids.clear();
+        self.apply(bids, asks);
+
+        let bids: Vec<(f64, f64)> = self.bids.iter().map(|(p, q)| (p.0, *q)).map(|l| self.decode(l)).collect();
+        let asks: Vec<(f64, f64)> = self.asks.iter().map(|(p, q)| (*p, *q)).map(|l| self.decode(l)).collect();
+
+        self.frame("snapshot", &bids, &asks)
+    }
+
+    pub fn update(&mut self, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> Value {
+        self.apply(bids, asks);
+        self.frame("update", bids, asks)
+    }
+
+    pub fn checksum(&self) -> u32 {
+        let mut checksum = String::new();
+
+        for (price, qty) in self.asks.iter().take(10) {
+            checksum.push_str(&format!("{}{}", price, qty));
+        }
+
+        for (price, qty) in self.bids.iter().take(10) {
+            checksum.push_str(&format!("{}{}", price.0, qty));
+        }
+
+        crc32fast::hash(checksum.as_bytes())
+    }
+
+    fn decode(&self, level: (i64, i64)) -> (f64, f64) {
+        (
+            Decimal::from_fixed(level.0, self.price_decimals).to_f64(),
+            Decimal::from_fixed(level.1, self.qty_decimals).to_f64(),
+        )
+    }
+
+    fn apply(&mut self, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        for (price, qty) in bids {
+            let key = encode(self.price_decimals, *price);
+            match encode(self.qty_decimals, *qty) {
+                0 => self.bids.remove(&Reverse(key)),
+                qty => self.bids.insert(Reverse(key), qty),
+            };
+        }
+
+        for (price, qty) in asks {
+            let key = encode(self.price_decimals, *price);
+            match encode(self.qty_decimals, *qty) {
+                0 => self.asks.remove(&key),
+                qty => self.asks.insert(key, qty),
+            };
+        }
+
+        if let Some((&key, _)) = self.asks.iter().nth(self.depth) {
+            self.asks.split_off(&key);
+        }
+
+        if let Some((&key, _)) = self.bids.iter().nth(self.depth) {
+            self.bids.split_off(&key);
+        }
+    }
+
+    fn frame(&self, type_field: &str, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> Value {
+        let levels = |side: &[(f64, f64)]| {
+            side.iter()
+                .map(|(price, qty)| json!({ "price": price, "qty": qty }))
+                .collect::<Vec<Value>>()
+        };
+
+        json!({
+            "channel": "book",
+            "type": type_field,
+            "data": [{
+                "symbol": self.symbol,
+                "bids": levels(bids),
+                "asks": levels(asks),
+                "checksum": self.checksum(),
+                "timestamp": now_rfc3339(),
+            }]
+        })
+    }
+}
+
+// a snapshot plus a few updates that add, change and remove levels
+pub fn book_frames(pair: &str, depth: usize) -> Vec<Value> {
+    let mut book = BookScript::new(pair, PRICE_DECIMALS, QTY_DECIMALS).depth(depth);
+
+    let bids: Vec<(f64, f64)> = (0..depth).map(|i| (40100. - i as f64 * 0.5, 0.25 + i as f64 / 8.)).collect();
+    let asks: Vec<(f64, f64)> = (0..depth).map(|i| (40100.1 + i as f64 * 0.5, 0.3 + i as f64 / 5.)).collect();
+
+    vec![
+        book.snapshot(&bids, &asks),
+        book.update(&[(40099.8, 0.75)], &[]),
+        book.update(&[], &[(40100.1, 0.), (40102.5, 1.1)]),
+        book.update(&[(40100.0, 0.12345678)], &[(40100.6, 2.5)]),
+    ]
+}
+
+// one level3 order event, `event` is add, modify or delete
+pub type L3Entry<'a> = (&'a str, &'a str, f64, f64);
+
+// BookScript for the level3 channel, keeps every order in queue order for the checksum
+#[derive(Debug, Clone)]
+pub struct L3Script {
+    symbol: String,
+    price_decimals: i32,
+    qty_decimals: i32,
+    asks: BTreeMap<i64, Vec<(String, i64)>>,
+    bids: BTreeMap<Reverse<i64>, Vec<(String, i64)>>,
+}
+
+impl L3Script {
+    pub fn new(symbol: &str, price_decimals: i32, qty_decimals: i32) -> Self {
+        Self {
+            symbol: symbol.into(),
+            price_decimals,
+            qty_decimals,
+            asks: BTreeMap::new(),
+            bids: BTreeMap::new(),
+        }
+    }
+
+    // orders as (order_id, price, qty)
+    pub fn snapshot(&mut self, bids: &[(&str, f64, f64)], asks: &[(&str, f64, f64)]) -> Value {
+        self.asks.clear();
+        self.bids.clear();
+
+        let bids: Vec<L3Entry> = bids.iter().map(|(id, price, qty)| ("add", *id, *price, *qty)).collect();
+        let asks: Vec<L3Entry> = asks.iter().map(|(id, price, qty)| ("add", *id, *price, *qty)).collect();
+        self.apply(&bids, &asks);
+
+        let order = |(_, id, price, qty): &L3Entry| json!({ "order_id": id, "limit_price": price, "order_qty": qty, "timestamp": now_rfc3339() });
+        self.frame("snapshot", bids.iter().map(order).collect(), asks.iter().map(order).collect())
+    }
+
+    pub fn update(&mut self, bids: &[L3Entry], asks: &[L3Entry]) -> Value {
+        self.apply(bids, asks);
+
+        let order = |(event, id, price, qty): &L3Entry| json!({ "event": event, "order_id": id, "limit_price": price, "order_qty": qty, "timestamp": now_rfc3339() });
+        self.frame("update", bids.iter().map(order).collect(), asks.iter().map(order).collect())
+    }
+
+    pub fn checksum(&self) -> u32 {
+        let mut checksum = String::new();
+
+        for (price, orders) in self.asks.iter().take(10) {
+            for (_, qty) in orders {
+                checksum.push_str(&format!("{}{}", price, qty));
+            }
+        }
+
+        for (price, orders) in self.bids.iter().take(10) {
+            for (_, qty) in orders {
+                checksum.push_str(&format!("{}{}", price.0, qty));
+            }
+        }
+
+        crc32fast::hash(checksum.as_bytes())
+    }
+
+    fn apply(&mut self, bids: &[L3Entry], asks: &[L3Entry]) {
+        for (event, id, price, qty) in bids {
+            let key = Reverse(encode(self.price_decimals, *price));
+            let qty = encode(self.qty_decimals, *qty);
+            Self::apply_order(&mut self.bids, key, event, id, qty);
+        }
+
+        for (event, id, price, qty) in asks {
+            let key = encode(self.price_decimals, *price);
+            let qty = encode(self.qty_decimals, *qty);
+            Self::apply_order(&mut self.asks, key, event, id, qty);
+        }
+    }
+
+    // a modify keeps the queue position, like kraken does for a partial fill
+    fn apply_order<K: Ord>(side: &mut BTreeMap<K, Vec<(String, i64)>>, key: K, event: &str, id: &str, qty: i64) {
+        let level = side.entry(key).or_default();
+
+        match event {
+            "add" => level.push((id.into(), qty)),
+            "modify" => level.iter_mut().filter(|o| o.0 == id).for_each(|o| o.1 = qty),
+            _ => level.retain(|o| o.0 != id),
+        }
+
+        side.retain(|_, level| !level.is_empty());
+    }
+
+    fn frame(&self, type_field: &str, bids: Vec<Value>, asks: Vec<Value>) -> Value {
+        json!({
+            "channel": "level3",
+            "type": type_field,
+            "data": [{
+                "symbol": self.symbol,
+                "bids": bids,
+                "asks": asks,
+                "checksum": self.checksum(),
+                "timestamp": now_rfc3339(),
+            }]
+        })
+    }
+}
+
+// two orders on each of `depth` levels, then a partial fill, a new order behind it and a cancel
+pub fn level3_frames(pair: &str, depth: usize) -> Vec<Value> {
+    let mut book = L3Script::new(pair, PRICE_DECIMALS, QTY_DECIMALS);
+
+    let ids: Vec<(String, String)> = (0..depth * 2).map(|i| (format!("OBID{i:02}-MOCK"), format!("OASK{i:02}-MOCK"))).collect();
+    let bids: Vec<(&str, f64, f64)> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.0.as_str(), 40100. - (i / 2) as f64 * 0.5, 0.1 + i as f64 / 20.))
+        .collect();
+    let asks: Vec<(&str, f64, f64)> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.1.as_str(), 40100.1 + (i / 2) as f64 * 0.5, 0.2 + i as f64 / 10.))
+        .collect();
+
+    vec![
+        book.snapshot(&bids, &asks),
+        book.update(&[("modify", "OBID00-MOCK", 40100.0, 0.05)], &[]),
+        book.update(&[("add", "OBNEW0-MOCK", 40100.0, 0.4)], &[("delete", "OASK01-MOCK", 40100.1, 0.3)]),
+    ]
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default()
+}
+
+fn encode(precision: i32, value: f64) -> i64 {
+    decimal(value).to_fixed(precision)
+}
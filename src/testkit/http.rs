@@ -0,0 +1,150 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use url::Url;
+
+use crate::{
+    auth::token::get_kraken_signature,
+    testkit::{MockState, fixtures},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl MockRequest {
+    pub fn param(&self, key: &str) -> Option<String> {
+        if let Some(value) = self.query.get(key) {
+            return Some(value.clone());
+        }
+
+        let body: Value = serde_json::from_str(&self.body).ok()?;
+        match body.get(key)? {
+            Value::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        }
+    }
+
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_str(&self.body).ok()
+    }
+}
+
+pub(crate) async fn serve(listener: TcpListener, state: Arc<Mutex<MockState>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, state).await;
+        });
+    }
+}
+
+async fn handle_connection(stream: TcpStream, state: Arc<Mutex<MockState>>) -> io::Result<()> {
+    let mut stream = BufReader::new(stream);
+
+    while let Some(request) = read_request(&mut stream).await? {
+        let response = respond(&state, request).to_string();
+
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            response.len()
+        );
+
+        stream.get_mut().write_all(head.as_bytes()).await?;
+        stream.get_mut().write_all(response.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+async fn read_request(stream: &mut BufReader<TcpStream>) -> io::Result<Option<MockRequest>> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length: usize = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await?;
+
+    let url = Url::parse(&format!("http://localhost{}", target)).map_err(io::Error::other)?;
+
+    Ok(Some(MockRequest {
+        method,
+        path: url.path().to_string(),
+        query: url.query_pairs().into_owned().collect(),
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }))
+}
+
+fn respond(state: &Mutex<MockState>, request: MockRequest) -> Value {
+    let mut state = state.lock().unwrap();
+    state.requests.push(request.clone());
+
+    if request.path.starts_with("/0/private/")
+        && let Err(error) = verify_private(&mut state, &request)
+    {
+        return fixtures::err(&[error]);
+    }
+
+    match state.routes.get(&request.path) {
+        Some(handler) => handler(&request),
+        None => fixtures::err(&["EGeneral:Unknown method"]),
+    }
+}
+
+// same checks kraken does: known key, valid API-Sign and a nonce that only goes up
+fn verify_private(state: &mut MockState, request: &MockRequest) -> Result<(), &'static str> {
+    if request.headers.get("api-key") != Some(&state.public_key) {
+        return Err("EAPI:Invalid key");
+    }
+
+    let body = request.json().ok_or("EGeneral:Invalid arguments")?;
+    let nonce: u128 = match body.get("nonce") {
+        Some(Value::String(nonce)) => nonce.parse().map_err(|_| "EAPI:Invalid nonce")?,
+        Some(Value::Number(nonce)) => nonce.to_string().parse().map_err(|_| "EAPI:Invalid nonce")?,
+        _ => return Err("EAPI:Invalid nonce"),
+    };
+
+    let sign = get_kraken_signature(&request.path, &body, &state.private_key, nonce).map_err(|_| "EAPI:Invalid key")?;
+    if request.headers.get("api-sign") != Some(&sign) {
+        return Err("EAPI:Invalid signature");
+    }
+
+    if state.check_nonce && nonce <= state.last_nonce {
+        return Err("EAPI:Invalid nonce");
+    }
+
+    state.last_nonce = nonce;
+    Ok(())
+}
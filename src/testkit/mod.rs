@@ -0,0 +1,264 @@
+pub mod fixtures;
+pub mod http;
+pub mod ws;
+
+#[cfg(all(test, feature = "testkit"))]
+mod tests;
+
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::Value;
+use tokio::{net::TcpListener, sync::broadcast, task::JoinHandle};
+
+use crate::{
+    Kraken, RateLimiter,
+    fetch::urls::{
+        ADD_ORDER_BATCH_URL, ADD_ORDER_URL, AMEND_ORDER_URL, ASSET_PAIRS_URL, ASSETS_URL, BALANCE_EX_URL, BALANCE_TRADE_URL, BALANCE_URL,
+        CANCEL_ALL_AFTER_URL, CANCEL_ALL_URL, CANCEL_ORDER_URL, CLOSED_ORDERS_URL, DEPTH_URL, EDIT_ORDER_URL, Endpoints, LEDGERS_URL, OHLC_URL,
+        OPEN_ORDERS_URL, QUERY_LEDGERS_URL, QUERY_ORDERS_URL, SERVER_TIME_URL, SPREAD_URL, SYSTEM_STATUS_URL, TICKER_URL, TRADES_HISTORY_URL,
+        TRADES_URL, WEBSOCKET_TOKEN_URL,
+    },
+    testkit::{http::MockRequest, ws::Control},
+};
+
+pub const MOCK_PUBLIC_KEY: &str = "mock-public-key";
+// base64 of "mock-private-key-for-signatures"
+pub const MOCK_PRIVATE_KEY: &str = "bW9jay1wcml2YXRlLWtleS1mb3Itc2lnbmF0dXJlcw==";
+
+pub type Handler = Arc<dyn Fn(&MockRequest) -> Value + Send + Sync>;
+
+pub(crate) struct MockState {
+    public_key: String,
+    private_key: String,
+    check_nonce: bool,
+    last_nonce: u128,
+    routes: HashMap<String, Handler>,
+    scripts: HashMap<String, Vec<Value>>,
+    requests: Vec<MockRequest>,
+    ws_requests: Vec<String>,
+}
+
+pub struct MockKraken {
+    state: MockState,
+}
+
+impl Default for MockKraken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockKraken {
+    pub fn new() -> Self {
+        let state = MockState {
+            public_key: MOCK_PUBLIC_KEY.into(),
+            private_key: MOCK_PRIVATE_KEY.into(),
+            check_nonce: true,
+            last_nonce: 0,
+            routes: HashMap::new(),
+            scripts: HashMap::new(),
+            requests: Vec::new(),
+            ws_requests: Vec::new(),
+        };
+
+        Self { state }
+            .route(SERVER_TIME_URL, |_| fixtures::server_time())
+            .route(ASSET_PAIRS_URL, |req| {
+                let pair = req.param("pair").unwrap_or("BTC/EUR".into());
+                serde_json::json!({ pair.clone(): fixtures::asset_pair(&pair) })
+            })
+            .route(OHLC_URL, |req| {
+                let pair = req.param("pair").unwrap_or("BTC/EUR".into());
+                let interval = req.param("interval").and_then(|i| i.parse().ok()).unwrap_or(1);
+                fixtures::ohlc(&pair, interval)
+            })
+            .route(TRADES_URL, |req| {
+                let pair = req.param("pair").unwrap_or("BTC/EUR".into());
+                let since = req.param("since").unwrap_or("0".into());
+                fixtures::trades(&pair, &since)
+            })
+            .route(TICKER_URL, |req| {
+                let pairs = req.param("pair").unwrap_or("BTC/EUR".into());
+                let tickers: serde_json::Map<String, Value> = pairs.split(',').map(|p| (p.to_string(), fixtures::ticker_info())).collect();
+                Value::Object(tickers)
+            })
+            .route(DEPTH_URL, |req| {
+                let pair = req.param("pair").unwrap_or("BTC/EUR".into());
+                let count = req.param("count").and_then(|c| c.parse().ok()).unwrap_or(100);
+                serde_json::json!({ pair: fixtures::depth(count) })
+            })
+            .route(SPREAD_URL, |req| {
+                let pair = req.param("pair").unwrap_or("BTC/EUR".into());
+                fixtures::spread(&pair)
+            })
+            .route(ASSETS_URL, |req| {
+                let assets = fixtures::assets();
+                match req.param("asset") {
+                    Some(names) => names
+                        .split(',')
+                        .filter_map(|a| assets.get(a).map(|v| (a.to_string(), v.clone())))
+                        .collect(),
+                    None => assets,
+                }
+            })
+            .route(SYSTEM_STATUS_URL, |_| fixtures::system_status())
+            .route(BALANCE_URL, |_| fixtures::balance())
+            .route(BALANCE_EX_URL, |_| fixtures::balance_ex())
+            .route(BALANCE_TRADE_URL, |_| fixtures::trade_balance())
+            .route(TRADES_HISTORY_URL, |req| {
+                let ofs = req.param("ofs").and_then(|ofs| ofs.parse().ok()).unwrap_or(0);
+                let start = req.param("start").and_then(|start| start.parse().ok());
+                fixtures::trades_history(ofs, start)
+            })
+            .route(LEDGERS_URL, |req| {
+                fixtures::ledgers(req.param("ofs").and_then(|ofs| ofs.parse().ok()).unwrap_or(0))
+            })
+            .route(QUERY_LEDGERS_URL, |req| fixtures::query_ledgers(&req.param("id").unwrap_or_default()))
+            .route(ADD_ORDER_URL, |_| fixtures::add_order())
+            .route(ADD_ORDER_BATCH_URL, |req| {
+                let count = req.json().and_then(|b| b["orders"].as_array().map(|o| o.len())).unwrap_or(0);
+                fixtures::add_order_batch(count)
+            })
+            .route(OPEN_ORDERS_URL, |_| fixtures::open_orders())
+            .route(CLOSED_ORDERS_URL, |_| fixtures::closed_orders())
+            .route(QUERY_ORDERS_URL, |req| {
+                let txids = req.json().and_then(|b| b["txid"].as_str().map(String::from)).unwrap_or_default();
+                fixtures::query_orders(&txids)
+            })
+            .route(EDIT_ORDER_URL, |req| {
+                let txid = req.json().and_then(|b| b["txid"].as_str().map(String::from)).unwrap_or_default();
+                fixtures::edit_order(&txid)
+            })
+            .route(AMEND_ORDER_URL, |_| fixtures::amend_order())
+            .route(CANCEL_ORDER_URL, |_| fixtures::cancel_order())
+            .route(CANCEL_ALL_URL, |_| fixtures::cancel_all())
+            .route(CANCEL_ALL_AFTER_URL, |req| {
+                let timeout = req.json().and_then(|b| b["timeout"].as_u64()).unwrap_or(0);
+                fixtures::cancel_all_after(timeout)
+            })
+            .route(WEBSOCKET_TOKEN_URL, |_| fixtures::websocket_token())
+    }
+
+    pub fn keys(mut self, public_key: &str, private_key: &str) -> Self {
+        self.state.public_key = public_key.into();
+        self.state.private_key = private_key.into();
+        self
+    }
+
+    pub fn check_nonce(mut self, check: bool) -> Self {
+        self.state.check_nonce = check;
+        self
+    }
+
+    // handler returns the `result` field, wrapped in an empty error list
+    pub fn route<F: Fn(&MockRequest) -> Value + Send + Sync + 'static>(self, path: &str, handler: F) -> Self {
+        self.handler(path, move |req| fixtures::ok(handler(req)))
+    }
+
+    // handler returns the full response, use this to answer with kraken errors
+    pub fn handler<F: Fn(&MockRequest) -> Value + Send + Sync + 'static>(mut self, path: &str, handler: F) -> Self {
+        self.state.routes.insert(path.into(), Arc::new(handler));
+        self
+    }
+
+    // frames sent after every subscribe ack on this channel, replacing the generated ones
+    pub fn script(mut self, channel: &str, frames: Vec<Value>) -> Self {
+        self.state.scripts.insert(channel.into(), frames);
+        self
+    }
+
+    pub async fn start(self) -> io::Result<MockServer> {
+        let http_listener = TcpListener::bind("127.0.0.1:0").await?;
+        let ws_listener = TcpListener::bind("127.0.0.1:0").await?;
+
+        let http_addr = http_listener.local_addr()?;
+        let ws_addr = ws_listener.local_addr()?;
+
+        let state = Arc::new(Mutex::new(self.state));
+        let (control, _) = broadcast::channel(256);
+
+        let tasks = vec![
+            tokio::spawn(http::serve(http_listener, state.clone())),
+            tokio::spawn(ws::serve(ws_listener, state.clone(), control.clone())),
+        ];
+
+        Ok(MockServer {
+            http_addr,
+            ws_addr,
+            state,
+            control,
+            tasks,
+        })
+    }
+}
+
+pub struct MockServer {
+    pub http_addr: SocketAddr,
+    pub ws_addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    control: broadcast::Sender<Control>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn endpoints(&self) -> Endpoints {
+        let ws = format!("ws://{}", self.ws_addr);
+
+        Endpoints::default()
+            .rest(&format!("http://{}", self.http_addr))
+            .ws_public(&ws)
+            .ws_private(&ws)
+            .ws_level3(&ws)
+    }
+
+    pub fn kraken(&self) -> Kraken {
+        let state = self.state.lock().unwrap();
+        Kraken::new(&state.public_key, &state.private_key)
+            .endpoints(self.endpoints())
+            .rate_limit(RateLimiter::unlimited())
+    }
+
+    pub fn route<F: Fn(&MockRequest) -> Value + Send + Sync + 'static>(&self, path: &str, handler: F) {
+        self.handler(path, move |req| fixtures::ok(handler(req)));
+    }
+
+    pub fn handler<F: Fn(&MockRequest) -> Value + Send + Sync + 'static>(&self, path: &str, handler: F) {
+        self.state.lock().unwrap().routes.insert(path.into(), Arc::new(handler));
+    }
+
+    pub fn script(&self, channel: &str, frames: Vec<Value>) {
+        self.state.lock().unwrap().scripts.insert(channel.into(), frames);
+    }
+
+    // send a frame to every connected socket
+    pub fn push(&self, frame: &Value) {
+        let _ = self.control.send(Control::Frame(frame.to_string()));
+    }
+
+    // cut every open socket without a close frame
+    pub fn drop_connections(&self) {
+        let _ = self.control.send(Control::Drop);
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn ws_requests(&self) -> Vec<Value> {
+        let state = self.state.lock().unwrap();
+        state.ws_requests.iter().filter_map(|r| serde_json::from_str(r).ok()).collect()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        for task in self.tasks.iter() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde_json::json;
+use tokio::{sync::mpsc::UnboundedReceiver, time::timeout};
+
+use crate::{
+    AddOrder, AuthFetchError, BalanceType, BookDepth, Ch, Channel, Kraken, L3OrderBook, OrderBook, OrderBookError, RateLimiter, RatePolicy,
+    RequestError, Socket, SocketError, Tier, TradeHistoryBody, Trades, WsEvent,
+    fetch::urls::WEBSOCKET_TOKEN_URL,
+    testkit::{
+        MOCK_PUBLIC_KEY, MockKraken,
+        fixtures::{self, BookScript, MOCK_HISTORY, PRICE_DECIMALS, QTY_DECIMALS},
+    },
+};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+// next event `select` keeps, anything else on the socket is skipped
+async fn next<T>(events: &mut UnboundedReceiver<WsEvent>, select: impl Fn(WsEvent) -> Option<T>) -> T {
+    loop {
+        let event = timeout(WAIT, events.recv()).await.expect("no event in time").expect("socket closed");
+        if let Some(found) = select(event) {
+            return found;
+        }
+    }
+}
+
+#[tokio::test]
+async fn rest_calls_round_trip() {
+    let server = MockKraken::new().start().await.unwrap();
+    let kraken = server.kraken();
+
+    let pair = kraken.get_asset_pair("ETH/EUR").await.unwrap();
+    assert_eq!(pair.wsname, "ETH/EUR");
+    assert_eq!(pair.pair_decimals, PRICE_DECIMALS);
+
+    let balance = kraken.get_balance(BalanceType::Base).await.unwrap();
+    assert!(balance.result.contains_key("XXBT"));
+
+    let names = kraken.get_asset_names().await.unwrap();
+    let balance = kraken.get_balance_normalized(BalanceType::Base, &names).await.unwrap();
+    assert_eq!(balance.result["BTC"].balance, "0.5".parse().unwrap());
+    assert!(balance.result.contains_key("EUR"));
+
+    // the mock recomputes API-Sign with get_kraken_signature, a call signed with another key is turned down
+    let forged = Kraken::new(MOCK_PUBLIC_KEY, "b3RoZXIta2V5")
+        .endpoints(server.endpoints())
+        .rate_limit(RateLimiter::unlimited());
+    assert!(forged.get_balance(BalanceType::Base).await.is_err());
+
+    let signed = server.requests().into_iter().filter(|r| r.path == "/0/private/Balance").count();
+    assert_eq!(signed, 3);
+}
+
+#[tokio::test]
+async fn trades_history_pages_through_every_offset() {
+    let server = MockKraken::new().start().await.unwrap();
+    let kraken = server.kraken();
+
+    let trades = kraken.get_all_trades_history(&TradeHistoryBody::default()).await.unwrap();
+    let ids: HashSet<&str> = trades.iter().map(|t| t.id.as_str()).collect();
+
+    assert_eq!(trades.len(), MOCK_HISTORY);
+    assert_eq!(ids.len(), MOCK_HISTORY);
+    assert!(trades.windows(2).all(|w| w[0].time >= w[1].time));
+}
+
+#[tokio::test]
+async fn update_trades_only_fetches_what_is_new() {
+    let server = MockKraken::new().start().await.unwrap();
+    let mut trades = Trades::new(Arc::new(server.kraken())).await.unwrap();
+    let held = trades.trades.len();
+
+    assert_eq!(trades.update_trades().await.unwrap(), 0);
+    assert_eq!(trades.trades.len(), held);
+
+    // an empty list takes one page, not the whole history
+    trades.trades.clear();
+    assert_eq!(trades.update_trades().await.unwrap(), held);
+
+    let history = server.requests().into_iter().filter(|r| r.path == "/0/private/TradesHistory").count();
+    assert_eq!(history, 3);
+}
+
+#[tokio::test]
+async fn scripted_book_matches_order_book_checksum() {
+    let server = MockKraken::new().start().await.unwrap();
+    let kraken = Arc::new(server.kraken());
+
+    let mut book = OrderBook::new(kraken, "BTC/EUR", BookDepth::D10).await.unwrap();
+    let frames = fixtures::book_frames("BTC/EUR", 10);
+    let expected = frames.last().unwrap()["data"][0]["checksum"].as_u64().unwrap() as u32;
+
+    let mut socket = Socket::new(vec![book.channel()]).endpoints(server.endpoints());
+    socket.start().await.unwrap();
+    socket.subscribe_to_channels(false).await;
+    let mut events = socket.recv_msg.take().unwrap();
+
+    for _ in 0..frames.len() {
+        let update = next(&mut events, |e| match e {
+            WsEvent::Book(update) => Some(update),
+            _ => None,
+        })
+        .await;
+        book.stream(update).unwrap();
+    }
+
+    assert_eq!(book.checksum(), expected);
+    assert_eq!(book.asks.len(), 10);
+    assert_eq!(book.bids.len(), 10);
+}
+
+#[tokio::test]
+async fn corrupted_book_frame_marks_the_book_stale() {
+    let mut script = BookScript::new("BTC/EUR", PRICE_DECIMALS, QTY_DECIMALS);
+    let snapshot = script.snapshot(&[(40100., 1.)], &[(40100.1, 1.)]);
+    let mut update = script.update(&[(40099.5, 2.)], &[]);
+    update["data"][0]["checksum"] = (script.checksum() ^ 1).into();
+
+    let server = MockKraken::new().script("book", vec![snapshot, update]).start().await.unwrap();
+    let mut book = OrderBook::new(Arc::new(server.kraken()), "BTC/EUR", BookDepth::D10).await.unwrap();
+
+    let mut socket = Socket::new(vec![book.channel()]).endpoints(server.endpoints());
+    socket.start().await.unwrap();
+    socket.subscribe_to_channels(false).await;
+    let mut events = socket.recv_msg.take().unwrap();
+
+    let mut results = Vec::new();
+    for _ in 0..2 {
+        let update = next(&mut events, |e| match e {
+            WsEvent::Book(update) => Some(update),
+            _ => None,
+        })
+        .await;
+        results.push(book.stream(update));
+    }
+
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(OrderBookError::ChecksumMismatch { .. })));
+    assert!(book.stale);
+    assert_eq!(book.checksum_mismatches, 1);
+}
+
+#[tokio::test]
+async fn requests_resolve_by_req_id() {
+    let server = MockKraken::new().start().await.unwrap();
+
+    let mut socket = Socket::new(Vec::new()).endpoints(server.endpoints());
+    socket.start().await.unwrap();
+
+    socket.ping().await.unwrap().await.unwrap();
+
+    let channel = Channel::new(Ch::TICKER, vec!["BTC/EUR", "ETH/EUR"], None);
+    let acks = socket.subscribe(channel).await.unwrap().await.unwrap();
+    let symbols: HashSet<String> = acks.into_iter().map(|a| a.symbol).collect();
+    assert_eq!(symbols, HashSet::from(["BTC/EUR".to_string(), "ETH/EUR".to_string()]));
+
+    let req_ids: Vec<i64> = server.ws_requests().iter().filter_map(|r| r["req_id"].as_i64()).collect();
+    assert_eq!(req_ids.len(), 2);
+    assert_ne!(req_ids[0], req_ids[1]);
+}
+
+#[tokio::test]
+async fn subscribe_merges_into_the_stored_channel() {
+    let server = MockKraken::new().start().await.unwrap();
+
+    let mut socket = Socket::new(Vec::new()).endpoints(server.endpoints());
+    socket.start().await.unwrap();
+
+    for pairs in [vec!["BTC/EUR"], vec!["BTC/EUR"], vec!["BTC/EUR", "ETH/EUR"]] {
+        let _ = socket.subscribe(Channel::new(Ch::TICKER, pairs, None)).await;
+    }
+    let _ = socket.subscribe(Channel::new(Ch::TRADE, vec!["BTC/EUR"], None)).await;
+
+    let channels = socket.channels();
+    assert_eq!(channels.len(), 2);
+    assert_eq!(channels[0].pairs(), ["BTC/EUR", "ETH/EUR"]);
+}
+
+#[tokio::test]
+async fn unsubscribe_leaves_channels_with_other_params() {
+    let server = MockKraken::new().start().await.unwrap();
+
+    let mut socket = Socket::new(Vec::new()).endpoints(server.endpoints());
+    socket.start().await.unwrap();
+
+    let book = |depth: usize| Channel::new(Ch::BOOK, vec!["BTC/EUR"], Some(HashMap::from([("depth".to_string(), json!(depth))])));
+    let _ = socket.subscribe(book(10)).await;
+    let _ = socket.subscribe(book(25)).await;
+    let _ = socket.unsubscribe(book(10)).await;
+
+    let channels = socket.channels();
+    assert_eq!(channels.len(), 1);
+    assert!(channels[0].same_params(&book(25)));
+    assert_eq!(channels[0].pairs(), ["BTC/EUR"]);
+}
+
+#[tokio::test]
+async fn reconnect_subscribes_again() {
+    let server = MockKraken::new().start().await.unwrap();
+    let book = OrderBook::new(Arc::new(server.kraken()), "BTC/EUR", BookDepth::D10).await.unwrap();
+
+    let mut socket = Socket::new(vec![book.channel()])
+        .endpoints(server.endpoints())
+        .backoff(Duration::from_millis(10), Duration::from_millis(50));
+    socket.start().await.unwrap();
+    socket.subscribe_to_channels(false).await;
+    let mut events = socket.recv_msg.take().unwrap();
+
+    next(&mut events, |e| matches!(e, WsEvent::SubscribeAck(_)).then_some(())).await;
+    server.drop_connections();
+
+    next(&mut events, |e| matches!(e, WsEvent::Resync).then_some(())).await;
+    next(&mut events, |e| matches!(e, WsEvent::SubscribeAck(_)).then_some(())).await;
+    next(&mut events, |e| matches!(e, WsEvent::Book(b) if b.type_field == "snapshot").then_some(())).await;
+
+    let subscribes = server.ws_requests().iter().filter(|r| r["method"] == "subscribe").count();
+    assert_eq!(subscribes, 2);
+    assert_eq!(socket.active_subscriptions().len(), 1);
+}
+
+#[tokio::test]
+async fn frames_queued_during_an_outage_are_not_replayed() {
+    let server = MockKraken::new().start().await.unwrap();
+
+    let mut socket = Socket::new(vec![Channel::new(Ch::TICKER, vec!["BTC/EUR"], None)])
+        .endpoints(server.endpoints())
+        .backoff(Duration::from_millis(300), Duration::from_millis(300));
+    socket.start().await.unwrap();
+    socket.subscribe_to_channels(false).await;
+    let mut events = socket.recv_msg.take().unwrap();
+
+    next(&mut events, |e| matches!(e, WsEvent::SubscribeAck(_)).then_some(())).await;
+    server.drop_connections();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let before = server.ws_requests().len();
+
+    let _ = socket.subscribe(Channel::new(Ch::TRADE, vec!["BTC/EUR"], None)).await;
+    let _ = socket.unsubscribe(Channel::new(Ch::TICKER, vec!["BTC/EUR"], None)).await;
+
+    next(&mut events, |e| matches!(e, WsEvent::Resync).then_some(())).await;
+    next(&mut events, |e| matches!(e, WsEvent::SubscribeAck(_)).then_some(())).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // only the stored channels are sent on the new connection
+    let sent: Vec<_> = server.ws_requests()[before..]
+        .iter()
+        .map(|r| (r["method"].clone(), r["params"]["channel"].clone()))
+        .collect();
+    assert_eq!(sent, vec![(json!("subscribe"), json!("trade"))]);
+}
+
+#[tokio::test]
+async fn order_sent_during_an_outage_is_never_placed() {
+    let server = MockKraken::new().start().await.unwrap();
+
+    let mut socket = Socket::private(Arc::new(server.kraken()), vec![Channel::new(Ch::EXECUTIONS, Vec::new(), None)])
+        .backoff(Duration::from_millis(300), Duration::from_millis(300));
+    socket.start().await.unwrap();
+    socket.subscribe_to_channels(false).await;
+    let mut events = socket.recv_msg.take().unwrap();
+
+    next(&mut events, |e| matches!(e, WsEvent::SubscribeAck(_)).then_some(())).await;
+    server.drop_connections();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let order = AddOrder {
+        pair: "BTC/EUR".into(),
+        volume: "0.1".parse().unwrap(),
+        ..Default::default()
+    };
+    let pending = socket.add_order(&order).await.unwrap();
+    assert!(matches!(pending.await, Err(RequestError::Closed(_))));
+
+    next(&mut events, |e| matches!(e, WsEvent::SubscribeAck(_)).then_some(())).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // a retry after Closed must not place it twice
+    assert!(server.ws_requests().iter().all(|r| r["method"] != "add_order"));
+}
+
+#[tokio::test]
+async fn socket_orders_share_the_per_pair_counter_with_rest() {
+    let server = MockKraken::new().start().await.unwrap();
+    let kraken = Arc::new(server.kraken().rate_limit(RateLimiter::new(Tier::Starter, RatePolicy::FailFast)));
+
+    let mut socket = Socket::private(kraken.clone(), Vec::new());
+    socket.start().await.unwrap();
+
+    let order = AddOrder {
+        pair: "BTC/EUR".into(),
+        volume: "0.1".parse().unwrap(),
+        ..Default::default()
+    };
+
+    // a starter account may have 60 orders a pair in flight
+    let mut pendings = Vec::new();
+    for _ in 0..60 {
+        pendings.push(socket.add_order(&order).await.unwrap());
+    }
+
+    assert!(matches!(socket.add_order(&order).await, Err(RequestError::RateLimited(_))));
+    assert!(matches!(kraken.post_add_order(&order).await, Err(AuthFetchError::RateLimited(_))));
+}
+
+#[tokio::test]
+async fn failed_token_fetch_reconnects_until_private_channels_are_back() {
+    // a token that expires right away is fetched again on every subscribe
+    let server = MockKraken::new()
+        .route(WEBSOCKET_TOKEN_URL, |_| json!({ "token": fixtures::MOCK_TOKEN, "expires": 0 }))
+        .start()
+        .await
+        .unwrap();
+
+    let mut socket = Socket::private(Arc::new(server.kraken()), vec![Channel::new(Ch::EXECUTIONS, Vec::new(), None)])
+        .backoff(Duration::from_millis(10), Duration::from_millis(20));
+    socket.start().await.unwrap();
+    socket.subscribe_to_channels(false).await;
+    let mut events = socket.recv_msg.take().unwrap();
+    let mut errors = socket.recv_err.take().unwrap();
+
+    next(&mut events, |e| matches!(e, WsEvent::SubscribeAck(_)).then_some(())).await;
+
+    server.handler(WEBSOCKET_TOKEN_URL, |_| fixtures::err(&["EService:Unavailable"]));
+    server.drop_connections();
+
+    // the dropped connection is reported first, then every attempt fails on the token until the endpoint recovers
+    let mut failed = 0;
+    while failed < 2 {
+        if let SocketError::Auth(_) = timeout(WAIT, errors.recv()).await.unwrap().unwrap() {
+            failed += 1;
+        }
+    }
+    server.route(WEBSOCKET_TOKEN_URL, |_| json!({ "token": fixtures::MOCK_TOKEN, "expires": 0 }));
+
+    next(&mut events, |e| matches!(e, WsEvent::Resync).then_some(())).await;
+    let ack = next(&mut events, |e| match e {
+        WsEvent::SubscribeAck(ack) => Some(ack),
+        _ => None,
+    })
+    .await;
+    assert_eq!(ack.result.channel, "executions");
+}
+
+#[tokio::test]
+async fn level3_stream_matches_checksum_and_queue() {
+    let server = MockKraken::new().start().await.unwrap();
+    let kraken = Arc::new(server.kraken());
+
+    let mut book = L3OrderBook::new(kraken.clone(), "BTC/EUR", BookDepth::D10).await.unwrap();
+    let mut socket = Socket::level3(kraken, vec![book.channel()]);
+    socket.start().await.unwrap();
+    socket.subscribe_to_channels(false).await;
+    let mut events = socket.recv_msg.take().unwrap();
+
+    for _ in 0..fixtures::level3_frames("BTC/EUR", 10).len() {
+        let update = next(&mut events, |e| match e {
+            WsEvent::Level3(update) => Some(update),
+            _ => None,
+        })
+        .await;
+        book.stream(update).unwrap();
+    }
+
+    // the partial fill keeps OBID00 at the front of 40100.0, OBNEW0 queues behind OBID01
+    assert_eq!(book.queue_position("OBID00-MOCK").map(|p| p.0), Some(0));
+    assert_eq!(book.queue_position("OBNEW0-MOCK").map(|p| p.0), Some(2));
+    assert!(book.order("OASK01-MOCK").is_none());
+}
+
+#[tokio::test]
+async fn level3_raises_depths_kraken_does_not_take() {
+    let server = MockKraken::new().start().await.unwrap();
+    let kraken = Arc::new(server.kraken());
+
+    let book = L3OrderBook::new(kraken.clone(), "BTC/EUR", BookDepth::D25).await.unwrap();
+    assert_eq!(book.depth, BookDepth::D100);
+
+    let mut socket = Socket::level3(kraken, Vec::new());
+    socket.start().await.unwrap();
+
+    let acks = socket.subscribe(book.channel()).await.unwrap().await.unwrap();
+    assert_eq!(acks[0].channel, "level3");
+
+    let rejected = Channel::new(Ch::LEVEL3, vec!["BTC/EUR"], Some(HashMap::from([("depth".to_string(), json!(25))])));
+    let error = socket.subscribe(rejected).await.unwrap().await;
+    assert!(matches!(error, Err(RequestError::Rejected(_))));
+}
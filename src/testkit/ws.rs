@@ -0,0 +1,190 @@
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast::{self, error::RecvError},
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+use crate::testkit::{MockState, fixtures};
+
+const PRIVATE_CHANNELS: [&str; 3] = ["executions", "balances", "level3"];
+const LEVEL3_DEPTHS: [u64; 3] = [10, 100, 1000];
+
+#[derive(Debug, Clone)]
+pub(crate) enum Control {
+    Frame(String),
+    Drop,
+}
+
+pub(crate) async fn serve(listener: TcpListener, state: Arc<Mutex<MockState>>, control: broadcast::Sender<Control>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let state = state.clone();
+        let control = control.subscribe();
+        tokio::spawn(async move {
+            handle_connection(stream, state, control).await;
+        });
+    }
+}
+
+async fn handle_connection(stream: TcpStream, state: Arc<Mutex<MockState>>, mut control: broadcast::Receiver<Control>) {
+    let Ok(ws) = accept_async(stream).await else {
+        return;
+    };
+
+    let (mut write, mut read) = ws.split();
+    let _ = write.send(Message::Text(status().to_string().into())).await;
+
+    loop {
+        tokio::select! {
+            msg = read.next() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    for frame in reply(&state, &text) {
+                        if write.send(Message::Text(frame.to_string().into())).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+            ctl = control.recv() => match ctl {
+                Ok(Control::Frame(frame)) => {
+                    let _ = write.send(Message::Text(frame.into())).await;
+                }
+                // dropping the stream without a close frame looks like a network failure to the client
+                Ok(Control::Drop) | Err(RecvError::Closed) => return,
+                Err(RecvError::Lagged(_)) => {}
+            },
+        }
+    }
+}
+
+fn status() -> Value {
+    json!({
+        "channel": "status",
+        "type": "update",
+        "data": [{ "api_version": "v2", "connection_id": 1, "system": "online", "version": "2.0.0" }]
+    })
+}
+
+fn reply(state: &Mutex<MockState>, text: &str) -> Vec<Value> {
+    let mut state = state.lock().unwrap();
+    state.ws_requests.push(text.to_string());
+
+    let Ok(request) = serde_json::from_str::<Value>(text) else {
+        return vec![json!({ "error": "EGeneral:Invalid arguments", "success": false })];
+    };
+
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let params = &request["params"];
+    let time_in = fixtures::now_rfc3339();
+
+    let mut frames = match method.as_str() {
+        "ping" => vec![json!({ "method": "pong" })],
+        "subscribe" | "unsubscribe" => subscription(&state, &method, params),
+        "add_order" | "batch_add" | "amend_order" | "edit_order" | "cancel_order" | "cancel_all" | "cancel_all_orders_after" => {
+            trading(&method, params)
+        }
+        _ => vec![json!({ "method": method, "error": "EGeneral:Unknown method", "success": false })],
+    };
+
+    for frame in frames.iter_mut().filter(|f| f.get("method").is_some()) {
+        if let Some(req_id) = request.get("req_id") {
+            frame["req_id"] = req_id.clone();
+        }
+        frame["time_in"] = json!(time_in);
+        frame["time_out"] = json!(fixtures::now_rfc3339());
+    }
+
+    frames
+}
+
+fn subscription(state: &MockState, method: &str, params: &Value) -> Vec<Value> {
+    let channel = params["channel"].as_str().unwrap_or_default();
+
+    if PRIVATE_CHANNELS.contains(&channel) && params["token"].as_str() != Some(fixtures::MOCK_TOKEN) {
+        return vec![json!({ "method": method, "error": "EAPI:Invalid token", "success": false })];
+    }
+
+    if channel == "level3" && params["depth"].as_u64().is_some_and(|depth| !LEVEL3_DEPTHS.contains(&depth)) {
+        return vec![json!({ "method": method, "error": "EGeneral:Invalid arguments:depth", "success": false })];
+    }
+
+    let symbols: Vec<String> = match params["symbol"].as_array() {
+        Some(symbols) => symbols.iter().filter_map(|s| s.as_str().map(String::from)).collect(),
+        None => vec![String::new()],
+    };
+
+    let mut frames = Vec::new();
+    for symbol in symbols.iter() {
+        let mut result = json!({ "channel": channel });
+        if !symbol.is_empty() {
+            result["symbol"] = json!(symbol);
+        }
+
+        for key in ["depth", "interval"] {
+            if let Some(value) = params.get(key) {
+                result[key] = value.clone();
+            }
+        }
+
+        if method == "subscribe" {
+            result["snapshot"] = params.get("snapshot").cloned().unwrap_or(json!(true));
+        }
+
+        frames.push(json!({ "method": method, "result": result, "success": true }));
+    }
+
+    if method == "unsubscribe" {
+        return frames;
+    }
+
+    match state.scripts.get(channel) {
+        Some(script) => frames.extend(script.iter().cloned()),
+        None => {
+            for symbol in symbols.iter() {
+                frames.extend(default_script(channel, symbol, params));
+            }
+        }
+    }
+
+    frames
+}
+
+fn trading(method: &str, params: &Value) -> Vec<Value> {
+    if params["token"].as_str() != Some(fixtures::MOCK_TOKEN) {
+        return vec![json!({ "method": method, "error": "EAPI:Invalid token", "success": false })];
+    }
+
+    let result = match method {
+        "add_order" => fixtures::ws_add_order(params),
+        "batch_add" => json!(
+            params["orders"]
+                .as_array()
+                .map(|o| o.iter().map(fixtures::ws_add_order).collect::<Vec<Value>>())
+        ),
+        "amend_order" => json!({ "amend_id": "TTW6PD-RC36L-ZZSWNU", "order_id": params["order_id"], "cl_ord_id": params["cl_ord_id"] }),
+        "edit_order" => json!({ "order_id": "OFVXHJ-KPQ3B-VS7ELA", "original_order_id": params["order_id"] }),
+        "cancel_order" => json!({ "order_id": params["order_id"][0] }),
+        "cancel_all" => json!({ "count": 1 }),
+        _ => fixtures::cancel_all_after(params["timeout"].as_u64().unwrap_or(0)),
+    };
+
+    vec![json!({ "method": method, "result": result, "success": true })]
+}
+
+fn default_script(channel: &str, symbol: &str, params: &Value) -> Vec<Value> {
+    match channel {
+        "book" => fixtures::book_frames(symbol, params["depth"].as_u64().unwrap_or(10) as usize),
+        "level3" => fixtures::level3_frames(symbol, params["depth"].as_u64().unwrap_or(10) as usize),
+        "ohlc" => vec![fixtures::ohlc_snapshot(symbol, params["interval"].as_i64().unwrap_or(1))],
+        "ticker" => vec![fixtures::ticker_snapshot(symbol)],
+        "trade" => vec![fixtures::trade_snapshot(symbol)],
+        "executions" => vec![fixtures::executions_snapshot()],
+        "balances" => vec![fixtures::balances_snapshot()],
+        _ => Vec::new(),
+    }
+}
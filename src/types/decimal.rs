@@ -0,0 +1,472 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::Sum,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DecimalError {
+    #[error("Invalid decimal: {0}")]
+    Invalid(String),
+
+    #[error("Decimal does not fit: {0}")]
+    Overflow(String),
+}
+
+// most digits a product keeps after the point, so chained products don't run out of mantissa
+const MAX_SCALE: u32 = 20;
+
+// fixed point number, `mantissa / 10^scale`, so "0.00012300" keeps every digit kraken sent
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal {
+        mantissa: 0,
+        scale: 0,
+    };
+
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        Self {
+            mantissa,
+            scale,
+        }
+    }
+
+    pub fn from_fixed(value: i64, scale: i32) -> Self {
+        Self::new(value as i128, scale.max(0) as u32)
+    }
+
+    // json numbers reach us as f64, its shortest round-trip form is the literal kraken sent
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        value.to_string().parse().ok()
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa < 0
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::new(self.mantissa.abs(), self.scale)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10_f64.powi(self.scale as i32)
+    }
+
+    // rounds half away from zero when digits are dropped, panics when adding digits overflows
+    pub fn rescale(&self, scale: u32) -> Self {
+        self.checked_rescale(scale).expect("Decimal overflow in rescale")
+    }
+
+    // None when adding digits overflows, dropping digits always fits
+    pub fn checked_rescale(&self, scale: u32) -> Option<Self> {
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => Some(*self),
+            Ordering::Greater => {
+                let factor = 10_i128.checked_pow(scale - self.scale)?;
+                Some(Self::new(self.mantissa.checked_mul(factor)?, scale))
+            }
+            Ordering::Less => {
+                // more digits dropped than an i128 holds, nothing is left to round
+                let Some(divisor) = 10_i128.checked_pow(self.scale - scale) else {
+                    return Some(Self::new(0, scale));
+                };
+
+                let quotient = self.mantissa / divisor;
+                let remainder = self.mantissa % divisor;
+
+                let rounded = match remainder.abs() >= divisor - remainder.abs() {
+                    true => quotient + self.mantissa.signum(),
+                    false => quotient,
+                };
+
+                Some(Self::new(rounded, scale))
+            }
+        }
+    }
+
+    // integer form at a fixed precision, as used for order book keys and the checksum.
+    // saturates at the i64 bounds instead of wrapping
+    pub fn to_fixed(&self, scale: i32) -> i64 {
+        let saturated = match self.is_negative() {
+            true => i64::MIN,
+            false => i64::MAX,
+        };
+
+        self.checked_rescale(scale.max(0) as u32)
+            .and_then(|value| i64::try_from(value.mantissa).ok())
+            .unwrap_or(saturated)
+    }
+
+    pub fn format(&self, decimals: i32) -> String {
+        self.rescale(decimals.max(0) as u32).to_string()
+    }
+
+    // division is not exact, the result is rounded to `scale` digits, None when dividing by zero or overflowing
+    pub fn checked_div(&self, rhs: Decimal, scale: u32) -> Option<Self> {
+        if rhs.is_zero() {
+            return None;
+        }
+
+        // one digit more than asked for, so `rescale` can round it
+        let exp = rhs.scale as i64 + scale as i64 + 1 - self.scale as i64;
+        let (numerator, denominator) = match exp >= 0 {
+            true => (self.mantissa.checked_mul(10_i128.checked_pow(exp as u32)?)?, rhs.mantissa),
+            false => (self.mantissa, rhs.mantissa.checked_mul(10_i128.checked_pow((-exp) as u32)?)?),
+        };
+
+        Some(Self::new(numerator / denominator, scale + 1).rescale(scale))
+    }
+
+    pub fn checked_add(&self, rhs: Decimal) -> Option<Self> {
+        let (a, b, scale) = self.checked_aligned(&rhs)?;
+        Some(Self::new(a.checked_add(b)?, scale))
+    }
+
+    pub fn checked_sub(&self, rhs: Decimal) -> Option<Self> {
+        let (a, b, scale) = self.checked_aligned(&rhs)?;
+        Some(Self::new(a.checked_sub(b)?, scale))
+    }
+
+    // scales add up on every product, trailing zeros are dropped and digits past MAX_SCALE are rounded away
+    pub fn checked_mul(&self, rhs: Decimal) -> Option<Self> {
+        let (a, b) = (self.normalize(), rhs.normalize());
+        let product = Self::new(a.mantissa.checked_mul(b.mantissa)?, a.scale + b.scale).normalize();
+
+        match product.scale > MAX_SCALE {
+            true => product.checked_rescale(MAX_SCALE),
+            false => Some(product),
+        }
+    }
+
+    // drops trailing zeros, "1.2300" becomes "1.23"
+    pub fn normalize(&self) -> Self {
+        let mut value = *self;
+        while value.scale > 0 && value.mantissa % 10 == 0 {
+            value.mantissa /= 10;
+            value.scale -= 1;
+        }
+        value
+    }
+
+    fn checked_aligned(&self, other: &Self) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.checked_rescale(scale)?.mantissa, other.checked_rescale(scale)?.mantissa, scale))
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = DecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DecimalError::Invalid(s.to_string());
+        let trimmed = s.trim();
+
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+
+        if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let joined = format!("{}{}", whole, fraction);
+        let mantissa: i128 = joined.parse().map_err(|_| DecimalError::Overflow(s.to_string()))?;
+
+        Ok(Self::new(if negative { -mantissa } else { mantissa }, fraction.len() as u32))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let digits = format!("{:0>width$}", digits, width = self.scale as usize + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - self.scale as usize);
+
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let Some((a, b, _)) = self.checked_aligned(other) {
+            return a.cmp(&b);
+        }
+
+        // scales too far apart to align, the sign decides first and then the magnitudes
+        let sign = self.mantissa.signum().cmp(&other.mantissa.signum());
+        let magnitude = cmp_magnitude((self.mantissa.unsigned_abs(), self.scale), (other.mantissa.unsigned_abs(), other.scale));
+
+        match self.is_negative() {
+            true => sign.then(magnitude.reverse()),
+            false => sign.then(magnitude),
+        }
+    }
+}
+
+// the digits both values have are compared first, the smaller scale keeps all of its own.
+// when those are equal the value with digits left over past that scale is the larger one
+fn cmp_magnitude(a: (u128, u32), b: (u128, u32)) -> Ordering {
+    if a.1 > b.1 {
+        return cmp_magnitude(b, a).reverse();
+    }
+
+    let (whole, rest) = match 10_u128.checked_pow(b.1 - a.1) {
+        Some(divisor) => (b.0 / divisor, b.0 % divisor),
+        None => (0, b.0),
+    };
+
+    a.0.cmp(&whole).then(match rest {
+        0 => Ordering::Equal,
+        _ => Ordering::Less,
+    })
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normal = self.normalize();
+        normal.mantissa.hash(state);
+        normal.scale.hash(state);
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect("Decimal overflow in add")
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs).expect("Decimal overflow in sub")
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.checked_mul(rhs).expect("Decimal overflow in mul")
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Self::Output {
+        Decimal::new(-self.mantissa, self.scale)
+    }
+}
+
+impl Sum for Decimal {
+    fn sum<I: Iterator<Item = Decimal>>(iter: I) -> Self {
+        iter.fold(Decimal::ZERO, |acc, d| acc + d)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Decimal::new(value as i128, 0)
+    }
+}
+
+// kraken sends strings on REST and numbers on the socket, both end up here
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DecimalVisitor;
+
+        impl de::Visitor<'_> for DecimalVisitor {
+            type Value = Decimal;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a decimal string or number")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Decimal, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Decimal, E> {
+                Ok(Decimal::from(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Decimal, E> {
+                Ok(Decimal::new(v as i128, 0))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Decimal, E> {
+                Decimal::from_f64(v).ok_or_else(|| E::custom(format!("Invalid decimal: {}", v)))
+            }
+        }
+
+        deserializer.deserialize_any(DecimalVisitor)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        hash::{DefaultHasher, Hash, Hasher},
+    };
+
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn parts(value: Decimal) -> (i128, u32) {
+        (value.mantissa(), value.scale())
+    }
+
+    #[test]
+    fn parses_sign_and_point() {
+        assert_eq!(parts(dec("-1.50")), (-150, 2));
+        assert_eq!(parts(dec("+2")), (2, 0));
+        assert_eq!(parts(dec(".5")), (5, 1));
+        assert_eq!(parts(dec("5.")), (5, 0));
+        assert_eq!(parts(dec(" 0.00012300 ")), (12300, 8));
+    }
+
+    #[test]
+    fn rejects_invalid_and_overflowing_input() {
+        for invalid in ["", ".", "-", "abc", "1.2.3", "1e5", "--1", "1,5"] {
+            assert_eq!(invalid.parse::<Decimal>(), Err(DecimalError::Invalid(invalid.to_string())), "{invalid:?}");
+        }
+
+        let long = "1".repeat(40);
+        assert_eq!(long.parse::<Decimal>(), Err(DecimalError::Overflow(long.clone())));
+    }
+
+    #[test]
+    fn displays_every_digit_of_the_scale() {
+        assert_eq!(Decimal::new(5, 3).to_string(), "0.005");
+        assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+        assert_eq!(Decimal::new(123, 0).to_string(), "123");
+        assert_eq!(dec("1.2300").to_string(), "1.2300");
+        assert_eq!(dec("-12.5").to_string(), "-12.5");
+    }
+
+    #[test]
+    fn rescale_rounds_half_away_from_zero() {
+        assert_eq!(dec("1.25").checked_rescale(1).unwrap().to_string(), "1.3");
+        assert_eq!(dec("-1.25").checked_rescale(1).unwrap().to_string(), "-1.3");
+        assert_eq!(dec("1.24").checked_rescale(1).unwrap().to_string(), "1.2");
+        assert_eq!(dec("-0.05").checked_rescale(1).unwrap().to_string(), "-0.1");
+        assert_eq!(dec("2.5").checked_rescale(0).unwrap().to_string(), "3");
+        assert_eq!(dec("1.5").checked_rescale(3).unwrap().to_string(), "1.500");
+
+        assert_eq!(Decimal::new(i128::MAX / 2, 0).checked_rescale(1), None);
+        assert_eq!(Decimal::new(1, 60).checked_rescale(0).unwrap(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn div_rounds_to_the_scale_asked_for() {
+        assert_eq!(dec("1").checked_div(dec("3"), 4).unwrap().to_string(), "0.3333");
+        assert_eq!(dec("2").checked_div(dec("3"), 4).unwrap().to_string(), "0.6667");
+        assert_eq!(dec("-2").checked_div(dec("3"), 4).unwrap().to_string(), "-0.6667");
+        assert_eq!(dec("0.5").checked_div(dec("0.25"), 2).unwrap().to_string(), "2.00");
+
+        assert_eq!(dec("1").checked_div(Decimal::ZERO, 4), None);
+        assert_eq!(dec("1").checked_div(dec("0.000"), 4), None);
+    }
+
+    #[test]
+    fn from_f64_keeps_the_shortest_literal() {
+        assert_eq!(parts(Decimal::from_f64(0.1).unwrap()), (1, 1));
+        assert_eq!(parts(Decimal::from_f64(-42.125).unwrap()), (-42125, 3));
+        assert_eq!(parts(Decimal::from_f64(1e-7).unwrap()), (1, 7));
+        assert_eq!(Decimal::from_f64(f64::NAN), None);
+        assert_eq!(Decimal::from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn equal_values_hash_alike() {
+        let hash = |value: Decimal| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        assert_eq!(dec("1.20"), dec("1.2"));
+        assert_eq!(hash(dec("1.20")), hash(dec("1.2")));
+        assert_eq!(HashSet::from([dec("1.20"), dec("1.2"), dec("1")]).len(), 2);
+    }
+
+    #[test]
+    fn compares_scales_too_far_apart_to_align() {
+        let tiny = dec("0.000000000000000000000000000001");
+        let big = dec("1000000000000");
+
+        assert!(tiny < big);
+        assert!(-big < -tiny);
+        assert!(-tiny < big);
+        assert!(Decimal::new(1, 50) < dec("0.5"));
+        assert!(Decimal::new(-1, 50) > dec("-0.5"));
+        assert_eq!(cmp_magnitude((10, 1), (10_u128.pow(38) + 1, 38)), Ordering::Less);
+        assert_eq!(cmp_magnitude((1, 0), (10_u128.pow(38), 38)), Ordering::Equal);
+    }
+}
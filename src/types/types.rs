@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::{fetch::body::Side, types::decimal::Decimal};
+
 #[derive(Serialize, Deserialize)]
 pub struct Nonce {
     pub nonce: String,
@@ -12,11 +14,13 @@ pub struct Nonce {
 #[serde(rename_all = "camelCase")]
 pub struct WebSocketKey {
     pub error: Vec<String>,
+    #[serde(default)]
     pub result: _Result,
 }
 
+// kraken sends an empty result next to an error
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct _Result {
     pub expires: i64,
     pub token: String,
@@ -26,7 +30,7 @@ pub struct _Result {
 #[serde(rename_all = "camelCase")]
 pub struct Ping {
     pub method: String,
-    #[serde(rename = "req_id")]
+    #[serde(rename = "req_id", default)]
     pub req_id: i64,
 }
 
@@ -35,12 +39,16 @@ pub struct Ping {
 pub struct SubRequest {
     pub method: String,
     pub params: Params,
+    #[serde(rename = "req_id", skip_serializing_if = "Option::is_none")]
+    pub req_id: Option<i64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Params {
     pub channel: String,
+    // private channels have no symbol
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub symbol: Vec<String>,
 
     #[serde(flatten)]
@@ -53,6 +61,8 @@ pub struct SubscribeAck {
     pub method: String,
     pub result: AckResult,
     pub success: bool,
+    #[serde(rename = "req_id", default)]
+    pub req_id: Option<i64>,
     #[serde(rename = "time_in")]
     pub time_in: String,
     #[serde(rename = "time_out")]
@@ -63,10 +73,52 @@ pub struct SubscribeAck {
 #[serde(rename_all = "camelCase")]
 pub struct AckResult {
     pub channel: String,
+    #[serde(default)]
     pub snapshot: bool,
+    #[serde(default)]
     pub symbol: String,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodError {
+    pub method: String,
+    pub error: String,
+    pub success: bool,
+    #[serde(rename = "req_id", default)]
+    pub req_id: Option<i64>,
+    #[serde(rename = "time_in", default)]
+    pub time_in: String,
+    #[serde(rename = "time_out", default)]
+    pub time_out: String,
+}
+
+// successful answer to a trading request, `result` depends on the method
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodResponse {
+    pub method: String,
+    #[serde(default)]
+    pub result: Value,
+    pub success: bool,
+    #[serde(default)]
+    pub req_id: Option<i64>,
+    #[serde(default)]
+    pub time_in: String,
+    #[serde(default)]
+    pub time_out: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusData {
+    #[serde(rename = "api_version")]
+    pub api_version: String,
+    #[serde(rename = "connection_id")]
+    pub connection_id: u64,
+    pub system: String,
+    pub version: String,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TickerType {
@@ -80,22 +132,22 @@ pub struct TickerType {
 #[serde(rename_all = "camelCase")]
 pub struct TickerData {
     pub symbol: String,
-    pub bid: f64,
+    pub bid: Decimal,
     #[serde(rename = "bid_qty")]
-    pub bid_qty: f64,
-    pub ask: f64,
+    pub bid_qty: Decimal,
+    pub ask: Decimal,
     #[serde(rename = "ask_qty")]
-    pub ask_qty: f64,
-    pub last: f64,
-    pub volume: f64,
-    pub vwap: f64,
-    pub low: f64,
-    pub high: f64,
-    pub change: f64,
+    pub ask_qty: Decimal,
+    pub last: Decimal,
+    pub volume: Decimal,
+    pub vwap: Decimal,
+    pub low: Decimal,
+    pub high: Decimal,
+    pub change: Decimal,
     #[serde(rename = "change_pct")]
-    pub change_pct: f64,
+    pub change_pct: Decimal,
     #[serde(rename = "volume_usd")]
-    pub volume_usd: f64,
+    pub volume_usd: Decimal,
     pub timestamp: String,
 }
 
@@ -120,15 +172,46 @@ pub struct OrderBookData {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Bid {
-    pub price: f64,
-    pub qty: f64,
+    pub price: Decimal,
+    pub qty: Decimal,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Ask {
-    pub price: f64,
-    pub qty: f64,
+    pub price: Decimal,
+    pub qty: Decimal,
+}
+
+// level3 channel, every resting order on its own instead of the sum per price
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct L3BookData {
+    pub symbol: String,
+    pub bids: Vec<L3Order>,
+    pub asks: Vec<L3Order>,
+    pub checksum: u32,
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum L3Event {
+    #[default]
+    Add,
+    Modify,
+    Delete,
+}
+
+// snapshots leave `event` out, every order in them is resting
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct L3Order {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<L3Event>,
+    pub order_id: String,
+    pub limit_price: Decimal,
+    pub order_qty: Decimal,
+    pub timestamp: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -137,6 +220,7 @@ pub struct KraSoc<T> {
     pub channel: String,
     #[serde(rename = "type")]
     pub type_field: String,
+    #[serde(default)]
     pub timestamp: String,
     pub data: Vec<T>,
 }
@@ -145,17 +229,112 @@ pub struct KraSoc<T> {
 #[serde(rename_all = "camelCase")]
 pub struct CandleStick {
     pub symbol: String,
-    pub open: f64,
-    pub high: f64,
-    pub low: f64,
-    pub close: f64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
     pub trades: i64,
-    pub volume: f64,
+    pub volume: Decimal,
     #[serde(skip)]
     pub epoch: u64,
-    pub vwap: f64,
+    pub vwap: Decimal,
     #[serde(rename = "interval_begin")]
     pub interval_begin: String,
     pub interval: i64,
     pub timestamp: String,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeData {
+    pub symbol: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub qty: Decimal,
+    #[serde(rename = "ord_type")]
+    pub ord_type: String,
+    #[serde(rename = "trade_id")]
+    pub trade_id: i64,
+    pub timestamp: String,
+}
+
+// executions channel, one entry per order event, fills carry the `last_*` fields
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionData {
+    pub order_id: String,
+    pub exec_type: String,
+    #[serde(default)]
+    pub exec_id: Option<String>,
+    #[serde(default)]
+    pub trade_id: Option<i64>,
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(default)]
+    pub side: Option<Side>,
+    #[serde(default)]
+    pub order_type: Option<String>,
+    #[serde(default)]
+    pub order_status: String,
+    #[serde(default)]
+    pub order_qty: Option<Decimal>,
+    #[serde(default)]
+    pub limit_price: Option<Decimal>,
+    #[serde(default)]
+    pub last_qty: Option<Decimal>,
+    #[serde(default)]
+    pub last_price: Option<Decimal>,
+    #[serde(default)]
+    pub liquidity_ind: Option<String>,
+    #[serde(default)]
+    pub cum_qty: Option<Decimal>,
+    #[serde(default)]
+    pub cum_cost: Option<Decimal>,
+    #[serde(default)]
+    pub avg_price: Option<Decimal>,
+    #[serde(default)]
+    pub cost: Option<Decimal>,
+    #[serde(default)]
+    pub fees: Vec<ExecutionFee>,
+    #[serde(default)]
+    pub cl_ord_id: Option<String>,
+    #[serde(default)]
+    pub order_userref: Option<i64>,
+    pub timestamp: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionFee {
+    pub asset: String,
+    pub qty: Decimal,
+}
+
+// balances channel, the snapshot lists every asset, updates are ledger entries
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceData {
+    pub asset: String,
+    #[serde(default)]
+    pub asset_class: String,
+    pub balance: Decimal,
+    #[serde(default)]
+    pub wallets: Vec<BalanceWallet>,
+    #[serde(default)]
+    pub ledger_id: Option<String>,
+    #[serde(default)]
+    pub ref_id: Option<String>,
+    #[serde(default, rename = "type")]
+    pub type_field: Option<String>,
+    #[serde(default)]
+    pub amount: Option<Decimal>,
+    #[serde(default)]
+    pub fee: Option<Decimal>,
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceWallet {
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub id: String,
+    pub balance: Decimal,
+}
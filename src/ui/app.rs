@@ -3,31 +3,40 @@ use std::{cmp::Reverse, collections::BTreeMap};
 use crate::{
     Trades,
     handler::{
-        candle::Candle,
+        candle::{Candle, CandleType},
+        market_trades::MarketTrades,
         orderbook::{self, OrderBook},
+        ticker::Ticker,
         trades,
     },
     types::types::{OrderBookData, OrderBookType},
-    utils::{decode_fixed, encode_fixed},
 };
 
 pub struct App {
     pub orderbook: OrderBook,
     pub candle: Candle,
     pub trades: Trades,
+    pub market_trades: MarketTrades,
+    pub ticker: Ticker,
+    // draw the depth chart where the candles are
+    pub depth_chart: bool,
 }
 
 pub enum Message {
     UpdateCandlesInterval(i64),
     UpdateCandlesPair(String),
+    UpdateCandleType(CandleType),
 }
 
 impl App {
-    pub fn new(orderbook: OrderBook, candle: Candle, trades: Trades) -> App {
+    pub fn new(orderbook: OrderBook, candle: Candle, trades: Trades, market_trades: MarketTrades, ticker: Ticker) -> App {
         App {
             orderbook,
             candle,
             trades,
+            market_trades,
+            ticker,
+            depth_chart: false,
         }
     }
 }
@@ -52,7 +52,7 @@ impl Button {
                 x: mouse.column,
                 y: mouse.row,
             };
-            if self.rect.contains((position)) {
+            if self.rect.contains(position) {
                 self.highlighted = true;
 
                 if let MouseEventKind::Down(_) = mouse.kind {
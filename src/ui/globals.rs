@@ -4,3 +4,6 @@ pub const BULL_COLOR: Color = Color::Rgb(52, 208, 88);
 pub const BEAR_COLOR: Color = Color::Rgb(234, 74, 90);
 
 pub const DULL_COLOR: Color = Color::Rgb(60, 60, 60);
+
+// pairs with a button and a ticker in the strip
+pub const WATCHLIST: [&str; 6] = ["BTC/EUR", "ETH/EUR", "SOL/EUR", "XRP/EUR", "ADA/EUR", "DOGE/EUR"];
@@ -105,12 +105,13 @@ impl Widget for Pixels {
 
             let mut pos = (p.x, p.y);
 
+            // mirrored inside `rect`, which does not have to start at the frame's origin
             if (self.flip_x) {
-                pos.0 = self.rect.width - pos.0 + 1;
+                pos.0 = 2 * self.rect.x + self.rect.width - 1 - pos.0;
             }
 
             if (self.flip_y) {
-                pos.1 = self.rect.height - pos.1 + 1;
+                pos.1 = 2 * self.rect.y + self.rect.height - 1 - pos.1;
             }
 
             buf[pos].set_char(p.char).set_style(style);
@@ -1,5 +1,5 @@
 use core::num;
-use std::{cmp, collections::BTreeMap, fmt::format, i32, sync::mpsc::Sender};
+use std::{cmp, collections::BTreeMap, fmt::format, sync::mpsc::Sender};
 
 use crossterm::event::MouseEvent;
 use ratatui::{
@@ -17,13 +17,16 @@ use tokio::fs::try_exists;
 
 use crate::{
     Message, epoch_to_rfc3339, epoch_to_timestamp,
-    fetch::types::Trade,
-    handler::{candle::Candle, trades},
-    types::types::CandleStick,
+    fetch::{body::Side, types::Trade},
+    handler::{candle::Candle, orderbook::OrderBook, trades},
+    types::{
+        decimal::Decimal,
+        types::{CandleStick, TickerData, TradeData},
+    },
     ui::{
         app::App,
         button::Button,
-        globals::{BEAR_COLOR, BULL_COLOR},
+        globals::{BEAR_COLOR, BULL_COLOR, DULL_COLOR},
         pixels::{Pixel, Pixels},
         ui_buttons::ui_buttons,
         utils::{abs_scale_rect, layout_block_f, layout_block_i, offset_rect, scale_rect},
@@ -33,18 +36,23 @@ use crate::{
 pub fn ui(mut frame: &mut Frame, app: &App, mouse: &Option<MouseEvent>, update: Sender<Message>) {
     let main_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
+        .constraints([Constraint::Length(6), Constraint::Percentage(75), Constraint::Percentage(25)])
         .split(frame.area());
 
-    let top_layout = Layout::default()
+    let ticker_layout = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
+        .constraints(app.ticker.pairs.iter().map(|_| Constraint::Fill(1)))
         .split(main_layout[0]);
 
+    let top_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(15), Constraint::Percentage(25)])
+        .split(main_layout[1]);
+
     let top_right_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(13), Constraint::Fill(1)])
-        .split(top_layout[1]);
+        .split(top_layout[2]);
 
     let orderbook_layout = Layout::default()
         .direction(Direction::Horizontal)
@@ -52,15 +60,48 @@ pub fn ui(mut frame: &mut Frame, app: &App, mouse: &Option<MouseEvent>, update:
         .split(top_right_layout[0]);
 
     let block_trades = Block::new().borders(Borders::ALL).title(" All executed trades ");
-    frame.render_widget(&block_trades, main_layout[1]);
+    frame.render_widget(&block_trades, main_layout[2]);
 
-    let title = format!(" Candle stick chart: {}, interval: {}m ", app.candle.pair, app.candle.interval);
+    let title = match app.depth_chart {
+        true => format!(" Depth chart: {}, press d for candles ", app.orderbook.pair),
+        false => format!(" Candle stick chart: {}, interval: {}m, press h for history ", app.candle.pair, app.candle.interval),
+    };
     let block_candle = Block::new().borders(Borders::ALL).title(title);
     frame.render_widget(&block_candle, top_layout[0]);
 
-    let block_orderbook = Block::new().borders(Borders::ALL).title(" Orderbook ");
+    let title = match app.orderbook.checksum_mismatches {
+        0 => " Orderbook ".to_string(),
+        n => format!(" Orderbook, resyncs: {} ", n),
+    };
+    let stats = match (app.orderbook.spread(), app.orderbook.spread_bps(), app.orderbook.imbalance(10)) {
+        (Some(spread), Some(bps), Some(imbalance)) => format!(" spread {spread} ({bps} bps), imbalance {imbalance} "),
+        _ => String::new(),
+    };
+    let block_orderbook = Block::new().borders(Borders::ALL).title(title).title_bottom(stats);
     frame.render_widget(&block_orderbook, top_right_layout[0]);
 
+    let block_sales = Block::new().borders(Borders::ALL).title(" Time and sales ");
+    frame.render_widget(&block_sales, top_layout[1]);
+
+    //
+    //
+    //
+
+    //
+    // ticker strip
+    //
+    for (pair, area) in app.ticker.pairs.iter().zip(ticker_layout.iter()) {
+        let border = match *pair == app.candle.pair {
+            true => Style::new().bold(),
+            false => Style::new().fg(DULL_COLOR),
+        };
+
+        let block = Block::new().borders(Borders::ALL).border_style(border).title(format!(" {pair} "));
+        let card = Paragraph::new(ticker_lines(app.ticker.get(pair))).block(block);
+
+        frame.render_widget(card, *area);
+    }
+
     //
     //
     //
@@ -74,27 +115,28 @@ pub fn ui(mut frame: &mut Frame, app: &App, mouse: &Option<MouseEvent>, update:
     let book_ask_area = &block_orderbook.inner(orderbook_layout[0]);
     let book_bid_area = &block_orderbook.inner(orderbook_layout[1]);
 
-    let ask_scale: i64 = book.asks.iter().map(|t| t.1.clone()).sum();
-    let bid_scale: i64 = book.bids.iter().map(|t| t.1.clone()).sum();
-    let max_scale = cmp::max(ask_scale, bid_scale);
+    // deeper books have more levels than rows, only the visible ones count towards the bars
+    let visible = book_ask_area.height.saturating_sub(1) as usize;
+
+    let max_scale = cmp::max(book.volume(Side::SELL, visible), book.volume(Side::BUY, visible)).to_f64();
 
     let mut scale_ask = 0.;
-    let ask_rows = book.asks.iter().map(|t| {
+    let ask_rows = book.asks.iter().take(visible).map(|t| {
         let (price, qty) = book.ask_decode(t);
 
         let width = book_ask_area.width as f64;
-        scale_ask += t.1.clone() as f64 / max_scale as f64;
+        scale_ask += qty.to_f64() / max_scale;
         let bar_width = (1. - scale_ask) * width;
 
         order_book_row(qty, price, width, bar_width, BEAR_COLOR, Color::Black)
     });
 
     let mut scale_bid = 0.;
-    let bid_rows = book.bids.iter().map(|t| {
+    let bid_rows = book.bids.iter().take(visible).map(|t| {
         let (price, qty) = book.bid_decode(t);
 
         let width = book_bid_area.width as f64;
-        scale_bid += t.1.clone() as f64 / max_scale as f64;
+        scale_bid += qty.to_f64() / max_scale;
         let bar_width = scale_bid * width;
 
         order_book_row(price, qty, width, bar_width, Color::Black, BULL_COLOR)
@@ -111,18 +153,42 @@ pub fn ui(mut frame: &mut Frame, app: &App, mouse: &Option<MouseEvent>, update:
     //
     //
 
+    //
+    // time and sales
+    //
+    let sales_area = block_sales.inner(top_layout[1]);
+    let visible = sales_area.height.saturating_sub(1) as usize;
+
+    let headers = ["TIME", "PRICE", "QTY", "TYPE"];
+    let widths = [Constraint::Length(8), Constraint::Fill(2), Constraint::Fill(2), Constraint::Length(4)];
+
+    let sales_rows = app.market_trades.prints.iter().take(visible).map(time_and_sales_row);
+    let sales_table = Table::new(sales_rows, widths).header(Row::new(headers).style(header_style));
+
+    frame.render_widget(sales_table, sales_area);
+
+    //
+    //
+    //
+
     //
     // candle sticks
     //
 
     let mut pixels = Pixels::new(&block_candle.inner(top_layout[0]));
-    let candle_range = app.candle.min_max(pixels.width() as usize);
-    let mut candle_pixels = build_candle_pixels(&pixels.rect, &app.candle.candles, candle_range, BULL_COLOR, BEAR_COLOR);
-
     pixels.flip_y = true;
-    pixels.flip_x = true;
 
-    pixels.add_pixels(&mut candle_pixels);
+    if app.depth_chart {
+        let mut depth_pixels = build_depth_pixels(&pixels.rect, &app.orderbook, BULL_COLOR, BEAR_COLOR);
+        pixels.add_pixels(&mut depth_pixels);
+    } else {
+        let candle_range = app.candle.min_max(pixels.width() as usize);
+        let mut candle_pixels = build_candle_pixels(&pixels.rect, app.candle.series(), candle_range, BULL_COLOR, BEAR_COLOR);
+
+        pixels.flip_x = true;
+        pixels.add_pixels(&mut candle_pixels);
+    }
+
     frame.render_widget(pixels, top_layout[0]);
 
     //
@@ -140,7 +206,7 @@ pub fn ui(mut frame: &mut Frame, app: &App, mouse: &Option<MouseEvent>, update:
     let trade_rows = trades_table_rows(&widths, &headers, &app.trades.trades);
     let trades_table = Table::new(trade_rows, widths).header(Row::new(headers).style(header_style));
 
-    frame.render_widget(trades_table, block_trades.inner(main_layout[1]));
+    frame.render_widget(trades_table, block_trades.inner(main_layout[2]));
 
     //
     //
@@ -149,7 +215,7 @@ pub fn ui(mut frame: &mut Frame, app: &App, mouse: &Option<MouseEvent>, update:
     //
     // buttons
     //
-    ui_buttons(top_right_layout[1], &mut frame, mouse, update);
+    ui_buttons(top_right_layout[1], frame, mouse, update);
 }
 
 fn trades_table_rows(widths: &[Constraint], headers: &[&str], trades: &[Trade]) -> Vec<Row<'static>> {
@@ -175,17 +241,102 @@ fn trades_table_rows(widths: &[Constraint], headers: &[&str], trades: &[Trade])
         .collect()
 }
 
-fn build_candle_pixels(rect: &Rect, candles: &Vec<CandleStick>, range: (f64, f64), bull: Color, bear: Color) -> Vec<Pixel> {
+// last, 24h change, high/low, volume and spread, empty until the first ticker arrived
+fn ticker_lines(ticker: Option<&TickerData>) -> Vec<Line<'static>> {
+    let Some(ticker) = ticker else {
+        return vec![Line::from("waiting for ticker").fg(DULL_COLOR)];
+    };
+
+    let color = if ticker.change.is_negative() { BEAR_COLOR } else { BULL_COLOR };
+    let sign = if ticker.change.is_negative() { "" } else { "+" };
+
+    vec![
+        Line::from(vec![
+            Span::from(ticker.last.to_string()).bold(),
+            Span::from(format!("  {sign}{}", ticker.change)).fg(color),
+        ]),
+        Line::from(format!("24h {sign}{}%", ticker.change_pct)).fg(color),
+        Line::from(format!("H {}  L {}", ticker.high, ticker.low)),
+        Line::from(format!("V {}  S {}", ticker.volume, ticker.ask - ticker.bid)),
+    ]
+}
+
+// colored by the aggressor, a buy lifted the offer and a sell hit the bid
+fn time_and_sales_row(print: &TradeData) -> Row<'static> {
+    let color = match print.side {
+        Side::BUY => BULL_COLOR,
+        Side::SELL => BEAR_COLOR,
+    };
+
+    // rfc3339, only the time of day fits
+    let time = print.timestamp.get(11..19).unwrap_or_default().to_string();
+    let ord_type = print.ord_type.get(..3).unwrap_or(&print.ord_type).to_uppercase();
+
+    Row::new(vec![
+        Cell::from(time),
+        Cell::from(print.price.to_string()),
+        Cell::from(print.qty.to_string()),
+        Cell::from(ord_type),
+    ])
+    .style(Style::new().fg(color))
+}
+
+// cumulative bid and ask volume as a stepped area, prices grow to the right and the spread stays empty
+fn build_depth_pixels(rect: &Rect, book: &OrderBook, bull: Color, bear: Color) -> Vec<Pixel> {
+    let (x, y, w, h) = layout_block_f(rect);
+
+    let to_f64 = |levels: Vec<(Decimal, Decimal)>| levels.iter().map(|(p, q)| (p.to_f64(), q.to_f64())).collect::<Vec<(f64, f64)>>();
+    let bids = to_f64(book.cumulative(Side::BUY));
+    let asks = to_f64(book.cumulative(Side::SELL));
+
+    let (Some(lowest), Some(highest)) = (bids.last(), asks.last()) else {
+        return Vec::new();
+    };
+
+    let range = (lowest.0, highest.0 - lowest.0);
+    let max_volume = lowest.1.max(highest.1);
+
+    let mut vec = Vec::new();
+    for column in 0..w as usize {
+        let price = range.0 + (column as f64 + 0.5) / w * range.1;
+
+        // every level at least as good as `price` is taken before it is reached
+        let (volume, color) = match (bids.iter().rfind(|b| b.0 >= price), asks.iter().rfind(|a| a.0 <= price)) {
+            (Some(bid), _) => (bid.1, bull),
+            (_, Some(ask)) => (ask.1, bear),
+            _ => continue,
+        };
+
+        let height = volume / max_volume * h;
+        for row in 0..height.ceil() as usize {
+            let fill = (height - row as f64).min(1.);
+            let pixel = Pixel::new(x as u16 + column as u16, y as u16 + row as u16)
+                .char(depth_block(fill))
+                .fg(color);
+            vec.push(pixel);
+        }
+    }
+
+    vec
+}
+
+// lower block elements, eighths of a cell
+fn depth_block(fill: f64) -> char {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    BLOCKS[((fill * 8.).ceil() as usize).clamp(1, 8) - 1]
+}
+
+fn build_candle_pixels(rect: &Rect, candles: &[CandleStick], range: (f64, f64), bull: Color, bear: Color) -> Vec<Pixel> {
     let (x, y, w, h) = layout_block_f(rect);
 
     let scaler = range.1 - range.0;
 
     let mut vec = Vec::new();
     for (index_x, candle) in candles.iter().enumerate().take(w as usize) {
-        let open = (candle.open - range.0) / scaler * h;
-        let high = (candle.high - range.0) / scaler * h;
-        let low = (candle.low - range.0) / scaler * h;
-        let close = (candle.close - range.0) / scaler * h;
+        let open = (candle.open.to_f64() - range.0) / scaler * h;
+        let high = (candle.high.to_f64() - range.0) / scaler * h;
+        let low = (candle.low.to_f64() - range.0) / scaler * h;
+        let close = (candle.close.to_f64() - range.0) / scaler * h;
 
         let start_cell = low.floor() as u64;
         let stop_cell = high.floor() as u64;
@@ -254,7 +405,7 @@ fn candle_stick(open: f64, high: f64, low: f64, close: f64) -> Option<char> {
     None
 }
 
-fn order_book_row(val1: f64, val2: f64, width: f64, bar_width: f64, color1: Color, color2: Color) -> Row<'static> {
+fn order_book_row(val1: Decimal, val2: Decimal, width: f64, bar_width: f64, color1: Color, color2: Color) -> Row<'static> {
     let half = width / 2.;
     let style1 = Style::new().bg(color1);
     let style2 = Style::new().bg(color2);
@@ -9,9 +9,9 @@ use ratatui::{
 };
 
 use crate::{
-    Button, Message,
+    Button, CandleType, Message,
     ui::{
-        globals::{BEAR_COLOR, BULL_COLOR, DULL_COLOR},
+        globals::{BEAR_COLOR, BULL_COLOR, DULL_COLOR, WATCHLIST},
         utils::{abs_scale_rect, offset_rect},
     },
 };
@@ -26,7 +26,21 @@ pub fn ui_buttons(button_area: Rect, frame: &mut Frame, mouse: &Option<MouseEven
     let button_width = candle_type.width / 2;
 
     let mut candle_stick = Button::new(button_width, 1, "Candle stick", &offset_rect(&candle_type, button_width * 0, 0)).bg(DULL_COLOR);
-    let mut heiken_ashi = Button::new(button_width, 1, "Heiken Ashi ", &&offset_rect(&candle_type, button_width * 1, 0)).bg(DULL_COLOR);
+    let mut heiken_ashi = Button::new(button_width, 1, "Heiken Ashi ", &offset_rect(&candle_type, button_width * 1, 0)).bg(DULL_COLOR);
+
+    candle_stick.callback({
+        let update = update.clone();
+        move || {
+            update.send(Message::UpdateCandleType(CandleType::CandleStick)).ok();
+        }
+    });
+
+    heiken_ashi.callback({
+        let update = update.clone();
+        move || {
+            update.send(Message::UpdateCandleType(CandleType::HeikinAshi)).ok();
+        }
+    });
 
     candle_stick.mouse(mouse);
     heiken_ashi.mouse(mouse);
@@ -45,52 +59,52 @@ pub fn ui_buttons(button_area: Rect, frame: &mut Frame, mouse: &Option<MouseEven
     let coin_pair = b_coin_pair.inner(button_area);
     let button_width = coin_pair.width / 2;
 
-    let mut coin_pair_0 = Button::new(button_width, 1, "BTC/EUR", &offset_rect(&coin_pair, button_width * 0, 0)).bg(DULL_COLOR);
-    let mut coin_pair_1 = Button::new(button_width, 1, "ETH/EUR", &&offset_rect(&coin_pair, button_width * 1, 0)).bg(DULL_COLOR);
-    let mut coin_pair_2 = Button::new(button_width, 1, "SOL/EUR", &offset_rect(&coin_pair, button_width * 0, 1)).bg(DULL_COLOR);
-    let mut coin_pair_3 = Button::new(button_width, 1, "XRP/EUR", &&offset_rect(&coin_pair, button_width * 1, 1)).bg(DULL_COLOR);
-    let mut coin_pair_4 = Button::new(button_width, 1, "ADA/EUR", &offset_rect(&coin_pair, button_width * 0, 2)).bg(DULL_COLOR);
-    let mut coin_pair_5 = Button::new(button_width, 1, "DOGE/EUR", &&offset_rect(&coin_pair, button_width * 1, 2)).bg(DULL_COLOR);
+    let mut coin_pair_0 = Button::new(button_width, 1, WATCHLIST[0], &offset_rect(&coin_pair, button_width * 0, 0)).bg(DULL_COLOR);
+    let mut coin_pair_1 = Button::new(button_width, 1, WATCHLIST[1], &offset_rect(&coin_pair, button_width * 1, 0)).bg(DULL_COLOR);
+    let mut coin_pair_2 = Button::new(button_width, 1, WATCHLIST[2], &offset_rect(&coin_pair, button_width * 0, 1)).bg(DULL_COLOR);
+    let mut coin_pair_3 = Button::new(button_width, 1, WATCHLIST[3], &offset_rect(&coin_pair, button_width * 1, 1)).bg(DULL_COLOR);
+    let mut coin_pair_4 = Button::new(button_width, 1, WATCHLIST[4], &offset_rect(&coin_pair, button_width * 0, 2)).bg(DULL_COLOR);
+    let mut coin_pair_5 = Button::new(button_width, 1, WATCHLIST[5], &offset_rect(&coin_pair, button_width * 1, 2)).bg(DULL_COLOR);
 
     coin_pair_0.callback({
         let update = update.clone();
         move || {
-            update.send(Message::UpdateCandlesPair("BTC/EUR".into())).ok();
+            update.send(Message::UpdateCandlesPair(WATCHLIST[0].into())).ok();
         }
     });
 
     coin_pair_1.callback({
         let update = update.clone();
         move || {
-            update.send(Message::UpdateCandlesPair("ETH/EUR".into())).ok();
+            update.send(Message::UpdateCandlesPair(WATCHLIST[1].into())).ok();
         }
     });
 
     coin_pair_2.callback({
         let update = update.clone();
         move || {
-            update.send(Message::UpdateCandlesPair("SOL/EUR".into())).ok();
+            update.send(Message::UpdateCandlesPair(WATCHLIST[2].into())).ok();
         }
     });
 
     coin_pair_3.callback({
         let update = update.clone();
         move || {
-            update.send(Message::UpdateCandlesPair("XRP/EUR".into())).ok();
+            update.send(Message::UpdateCandlesPair(WATCHLIST[3].into())).ok();
         }
     });
 
     coin_pair_4.callback({
         let update = update.clone();
         move || {
-            update.send(Message::UpdateCandlesPair("ADA/EUR".into())).ok();
+            update.send(Message::UpdateCandlesPair(WATCHLIST[4].into())).ok();
         }
     });
 
     coin_pair_5.callback({
         let update = update.clone();
         move || {
-            update.send(Message::UpdateCandlesPair("DOGE/EUR".into())).ok();
+            update.send(Message::UpdateCandlesPair(WATCHLIST[5].into())).ok();
         }
     });
 
@@ -120,7 +134,7 @@ pub fn ui_buttons(button_area: Rect, frame: &mut Frame, mouse: &Option<MouseEven
     let b_time_w = candle_time.width / 3;
 
     let mut m1 = Button::new(b_time_w, 1, "1m", &offset_rect(&candle_time, b_time_w * 0, 0)).bg(DULL_COLOR);
-    let mut m5 = Button::new(b_time_w, 1, "5m", &&offset_rect(&candle_time, b_time_w * 1, 0)).bg(DULL_COLOR);
+    let mut m5 = Button::new(b_time_w, 1, "5m", &offset_rect(&candle_time, b_time_w * 1, 0)).bg(DULL_COLOR);
     let mut m15 = Button::new(b_time_w, 1, "15m", &offset_rect(&candle_time, b_time_w * 2, 0)).bg(DULL_COLOR);
     let mut m30 = Button::new(b_time_w, 1, "30m", &offset_rect(&candle_time, b_time_w * 0, 1)).bg(DULL_COLOR);
     let mut h1 = Button::new(b_time_w, 1, "1h", &offset_rect(&candle_time, b_time_w * 1, 1)).bg(DULL_COLOR);
@@ -34,8 +34,8 @@ pub fn abs_scale_rect(rect: &Rect, width: u16, height: u16) -> Rect {
     Rect {
         x: rect.x,
         y: rect.y,
-        height: height,
-        width: width,
+        height,
+        width,
     }
 }
 
@@ -24,16 +24,6 @@ pub fn nested_object<T: DeserializeOwned>(path: &str, json: &mut Value) -> Resul
     Ok(asset)
 }
 
-pub fn encode_fixed(precision: i32, value: f64) -> i64 {
-    let scale = 10_f64.powi(precision);
-    (value * scale).round() as i64
-}
-
-pub fn decode_fixed(precision: i32, value: i64) -> f64 {
-    let scale = 10_f64.powi(precision);
-    value as f64 / scale
-}
-
 pub fn rfc3339_to_epoch(time: &str) -> u64 {
     let dt: DateTime<Utc> = DateTime::parse_from_rfc3339(time)
         .expect("Unable to convert timestamp")